@@ -11,30 +11,31 @@ use rust_clustering::clustering::kmedians::*;
 use rust_clustering::clustering::kmedoids::*;
 use rust_clustering::clustering::mini_batch_kmeans::*;
 use rust_clustering::point::*;
+use rust_clustering::statistics::distance::Manhattan;
 use test::Bencher;
 use rand::*;
 
 #[bench]
 fn bench_1000_points_agglomerative(b: &mut Bencher) {
     let mut rng = rand::thread_rng();
-    let mut points: Vec<Point> = (0..1000).map(|_| {
+    let points: Vec<Point> = (0..1000).map(|_| {
         Point::new((0..2).into_iter().map(|_| rng.next_f64()).collect())
     }).collect();
 
     b.iter(|| {
-        Agglomerative::run(points.as_mut_slice(), 10, &Link::Centroid)
+        Agglomerative::new().set_link(Link::Centroid).run(&points, 10)
     });
 }
 
 #[bench]
 fn bench_100000_points_fuzzy_cmeans(b: &mut Bencher) {
     let mut rng = rand::thread_rng();
-    let mut points: Vec<Point> = (0..100000).map(|_| {
+    let points: Vec<Point> = (0..100000).map(|_| {
         Point::new((0..2).into_iter().map(|_| rng.next_f64()).collect())
     }).collect();
 
     b.iter(|| {
-        FuzzyCMeans::run(points.as_mut_slice(), 10, 15, 2.0, 0.00001, FuzzyCMeansInitialization::Random, None);
+        FuzzyCMeans::new().set_max_iterations(15).set_init_method(FuzzyCMeansInitialization::Random).run(&points, 10);
     });
 }
 
@@ -65,12 +66,14 @@ fn bench_100000_points_kmedians(b: &mut Bencher) {
 #[bench]
 fn bench_100000_points_kmedoids(b: &mut Bencher) {
     let mut rng = rand::thread_rng();
-    let mut points: Vec<Point> = (0..100000).map(|_| {
+    let points: Vec<Point> = (0..100000).map(|_| {
         Point::new((0..2).into_iter().map(|_| rng.next_f64()).collect())
     }).collect();
 
     b.iter(|| {
-        KMedoids::new().run(points.as_mut_slice(), 10)
+        KMedoids::new().set_algorithm(KMedoidsAlgorithm::Alternate)
+                       .set_init_method(KMedoidsInitialization::KMeansPlusPlus)
+                       .run::<Manhattan>(&points, 10)
     });
 }
 
@@ -0,0 +1,242 @@
+// http://www.psi.toronto.edu/affinitypropagation/FreyDueckScience07.pdf
+
+use rand;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::f64;
+use point::Point;
+use statistics::distance::Distance;
+use rayon::prelude::*;
+
+pub struct AffinityPropagation {
+    assignments: Vec<usize>,
+    medoids: Vec<usize>,
+    iterations: usize,
+    converged: bool,
+    damping: f64,
+    preference: Option<f64>,
+    max_iterations: usize,
+    convergence_iterations: usize
+}
+
+impl Default for AffinityPropagation {
+    fn default() -> AffinityPropagation {
+        AffinityPropagation {
+            assignments: vec![],
+            medoids: vec![],
+            iterations: 0,
+            converged: false,
+            damping: 0.9,
+            preference: None,
+            max_iterations: 200,
+            convergence_iterations: 15
+        }
+    }
+}
+
+impl AffinityPropagation {
+    pub fn new() -> Self {
+        AffinityPropagation::default()
+    }
+
+    /// Clusters with the negated distances as similarities
+    pub fn run<D: Distance>(self, points: &[Point]) -> Self {
+        let similarities: Vec<Vec<f64>> = points.par_iter().map(|p| {
+            points.iter().map(|q| -D::distance(p.coordinates(), q.coordinates())).collect()
+        }).collect();
+
+        self.run_precomputed(&similarities)
+    }
+
+    /// Clusters from a similarity matrix, where larger means more similar. The diagonal is replaced by the preference.
+    pub fn run_precomputed(self, similarities: &[Vec<f64>]) -> Self {
+        let n = similarities.len();
+        let preference = match self.preference {
+            Some(preference) => preference,
+            None => Self::median(similarities.iter().enumerate().flat_map(|(i, row)| {
+                row.iter().enumerate().filter(move |&(k, _)| k != i).map(|(_, s)| *s)
+            }).collect())
+        };
+
+        // Tiny noise breaks the ties that make the messages oscillate
+        let mut rng = rand::thread_rng();
+        let s: Vec<Vec<f64>> = similarities.iter().enumerate().map(|(i, row)| {
+            row.iter().enumerate().map(|(k, value)| {
+                let value = if i == k { preference } else { *value };
+                value + (f64::EPSILON * value.abs() + f64::MIN_POSITIVE * 100.0) * rng.next_f64()
+            }).collect()
+        }).collect();
+
+        let damping = self.damping;
+        let mut responsibilities = vec![vec![0.0; n]; n];
+        let mut availabilities = vec![vec![0.0; n]; n];
+        let mut exemplars: Vec<usize> = vec![];
+        let mut unchanged = 0;
+        let mut i = 0;
+
+        while i < self.max_iterations {
+            // r(i, k) = s(i, k) - max over k' != k of a(i, k') + s(i, k')
+            responsibilities.par_iter_mut().zip(availabilities.par_iter()).zip(s.par_iter()).for_each(|((r, a), s)| {
+                let (first, first_value, second_value) = (0..n).fold((0, f64::NEG_INFINITY, f64::NEG_INFINITY), |(first, first_value, second_value), k| {
+                    let value = a[k] + s[k];
+
+                    if value > first_value {
+                        (k, value, first_value)
+                    } else {
+                        (first, first_value, second_value.max(value))
+                    }
+                });
+
+                for k in 0..n {
+                    let max = if k == first { second_value } else { first_value };
+                    r[k] = damping * r[k] + (1.0 - damping) * (s[k] - max);
+                }
+            });
+
+            // a(i, k) = min(0, r(k, k) + sum over i' not in {i, k} of max(0, r(i', k))), a(k, k) without the min
+            let column_sums: Vec<f64> = (0..n).into_par_iter().map(|k| {
+                responsibilities.iter().enumerate().map(|(index_i, r)| if index_i == k { r[k] } else { r[k].max(0.0) }).sum()
+            }).collect();
+
+            availabilities.par_iter_mut().zip(responsibilities.par_iter()).enumerate().for_each(|(index_i, (a, r))| {
+                for k in 0..n {
+                    let updated = match index_i == k {
+                        true => column_sums[k] - r[k],
+                        false => (column_sums[k] - r[k].max(0.0)).min(0.0)
+                    };
+
+                    a[k] = damping * a[k] + (1.0 - damping) * updated;
+                }
+            });
+
+            let updated_exemplars: Vec<usize> = (0..n).filter(|k| availabilities[*k][*k] + responsibilities[*k][*k] > 0.0).collect();
+
+            i += 1;
+
+            unchanged = match updated_exemplars == exemplars {
+                true => unchanged + 1,
+                false => 0
+            };
+
+            exemplars = updated_exemplars;
+            if !exemplars.is_empty() && unchanged >= self.convergence_iterations {
+                break;
+            }
+        }
+
+        let converged = !exemplars.is_empty() && unchanged >= self.convergence_iterations;
+        if exemplars.is_empty() {
+            return AffinityPropagation { assignments: vec![], medoids: vec![], iterations: i, converged, .. self }
+        }
+
+        // Refine every exemplar to the member most similar to the rest of its cluster
+        let assignments = Self::assign(&s, &exemplars);
+        let medoids: Vec<usize> = (0..exemplars.len()).map(|index_c| {
+            let members: Vec<usize> = (0..n).filter(|index_p| assignments[*index_p] == index_c).collect();
+
+            *members.iter().max_by(|a, b| {
+                let a_total = members.iter().map(|m| s[*m][**a]).sum::<f64>();
+                let b_total = members.iter().map(|m| s[*m][**b]).sum::<f64>();
+                a_total.partial_cmp(&b_total).unwrap_or(Ordering::Equal)
+            }).unwrap()
+        }).collect();
+
+        AffinityPropagation {
+            assignments: Self::assign(&s, &medoids),
+            medoids,
+            iterations: i,
+            converged,
+            .. self
+        }
+    }
+
+    // Every exemplar labels itself, other points go to their most similar exemplar
+    fn assign(s: &[Vec<f64>], exemplars: &[usize]) -> Vec<usize> {
+        (0..s.len()).map(|index_p| {
+            match exemplars.iter().position(|e| *e == index_p) {
+                Some(index_c) => index_c,
+                None => (0..exemplars.len()).max_by(|a, b| {
+                    s[index_p][exemplars[*a]].partial_cmp(&s[index_p][exemplars[*b]]).unwrap_or(Ordering::Equal)
+                }).unwrap()
+            }
+        }).collect()
+    }
+
+    fn median(mut values: Vec<f64>) -> f64 {
+        if values.is_empty() {
+            return 0.0
+        }
+
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        let middle = values.len() / 2;
+
+        match values.len() % 2 {
+            0 => (values[middle - 1] + values[middle]) / 2.0,
+            _ => values[middle]
+        }
+    }
+
+    /// Cluster of every point, empty if no exemplar emerged
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Indices of the exemplars, one per cluster
+    pub fn medoids(&self) -> &[usize] { &self.medoids }
+
+    pub fn no_clusters(&self) -> usize { self.medoids.len() }
+
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    /// Weight of the previous messages in every update, in [0.5, 1)
+    pub fn set_damping(self, damping: f64) -> Self {
+        AffinityPropagation { damping, .. self }
+    }
+
+    /// Self-similarity of every point, higher values giving more clusters. Defaults to the median similarity.
+    pub fn set_preference(self, preference: f64) -> Self {
+        AffinityPropagation { preference: Some(preference), .. self }
+    }
+
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        AffinityPropagation { max_iterations, .. self }
+    }
+
+    /// Number of iterations the exemplars must stay the same to converge
+    pub fn set_convergence_iterations(self, convergence_iterations: usize) -> Self {
+        AffinityPropagation { convergence_iterations, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statistics::distance::SquaredEuclidean;
+
+    #[test]
+    fn can_run_affinity_propagation() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = [(0.0, 0.0), (5.0, 5.0), (-5.0, 5.0)].iter().flat_map(|&(x, y)| {
+            (0..30).map(|_| Point::new(vec![x + rng.next_f64(), y + rng.next_f64()])).collect::<Vec<Point>>()
+        }).collect();
+
+        let output = AffinityPropagation::new().run::<SquaredEuclidean>(&points);
+
+        assert!(output.converged());
+        assert_eq!(3, output.no_clusters());
+        for blob in 0..3 {
+            assert!(output.assignments()[blob * 30..(blob + 1) * 30].iter().all(|a| *a == output.assignments()[blob * 30]));
+            assert_eq!(blob, output.medoids()[output.assignments()[blob * 30]] / 30);
+        }
+    }
+
+    #[test]
+    fn preference_controls_the_number_of_clusters() {
+        let points: Vec<Point> = (0..20).map(|i| Point::new(vec![i as f64])).collect();
+
+        let few = AffinityPropagation::new().set_preference(-1000.0).run::<SquaredEuclidean>(&points);
+        let many = AffinityPropagation::new().set_preference(-1.0).run::<SquaredEuclidean>(&points);
+
+        assert!(few.no_clusters() < many.no_clusters());
+    }
+}
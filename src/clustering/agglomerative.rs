@@ -3,218 +3,752 @@ use std::usize;
 use std::f64;
 use point::Point;
 use clustering::agglomerative::Link::*;
-use statistics::distance::{Distance, SquaredEuclidean};
-use statistics::statistics::Statistics;
+use statistics::distance::{Distance, Euclidean, SquaredEuclidean};
+use experimental::kdtree::KDTree;
+use clustering::dendrogram::Dendrogram;
 use rayon::prelude::*;
 
+#[derive(Copy, Clone, Debug)]
 pub enum Link {
     Single,
     Complete,
+    /// UPGMA, the mean distance between the points of both clusters
     Average,
-    Centroid
+    /// WPGMA, the mean of the distances of both merged clusters, regardless of their sizes
+    Weighted,
+    Centroid,
+    /// WPGMC, the centroid of a merged cluster being the midpoint of both centroids
+    Median,
+    /// Increase of the within-cluster sum of squares
+    Ward
 }
 
-struct Cluster {
-    points: Vec<usize>,
-    centroid: Vec<f64>
+impl Link {
+    // Centroid, median and Ward only hold on squared euclidean distances
+    fn is_squared(&self) -> bool {
+        match *self {
+            Centroid | Median | Ward => true,
+            _ => false
+        }
+    }
+
+    // Lance-Williams distance from cluster k to the union of clusters i and j
+    fn update(&self, d_ki: f64, d_kj: f64, d_ij: f64, n_i: usize, n_j: usize, n_k: usize) -> f64 {
+        let (n_i, n_j, n_k) = (n_i as f64, n_j as f64, n_k as f64);
+
+        match *self {
+            Single => d_ki.min(d_kj),
+            Complete => d_ki.max(d_kj),
+            Average => (n_i * d_ki + n_j * d_kj) / (n_i + n_j),
+            Weighted => 0.5 * (d_ki + d_kj),
+            Centroid => (n_i * d_ki + n_j * d_kj) / (n_i + n_j) - n_i * n_j * d_ij / ((n_i + n_j) * (n_i + n_j)),
+            Median => 0.5 * (d_ki + d_kj) - 0.25 * d_ij,
+            Ward => ((n_i + n_k) * d_ki + (n_j + n_k) * d_kj - n_k * d_ij) / (n_i + n_j + n_k)
+        }
+    }
+}
+
+/// Algorithm building the dendrogram. One that cannot compute the chosen link gives way to the default for that link.
+#[derive(Copy, Clone, Debug)]
+pub enum AgglomerativeAlgorithm {
+    /// Merges the closest pair through a distance matrix with cached nearest neighbors, O(n²) memory.
+    /// Any link, and the default for centroid and median linkage.
+    Generic,
+    /// Sibson's pointer representation, O(n) memory. Single linkage only, and its default.
+    SLINK,
+    /// Defays' pointer representation, O(n) memory but not always the exact complete linkage. Complete linkage only.
+    CLINK,
+    /// Nearest-neighbor chain for single, complete, average, weighted and Ward linkage, the default for the last four.
+    /// Ward linkage needs O(n) memory, the others a distance matrix in O(n²) memory, about 3.2 GB for 20000 points,
+    /// so large data sets call for SLINK or CLINK.
+    NNChain
+}
+
+impl AgglomerativeAlgorithm {
+    fn computes(&self, link: Link) -> bool {
+        match (*self, link) {
+            (AgglomerativeAlgorithm::Generic, _) => true,
+            (AgglomerativeAlgorithm::SLINK, Single) => true,
+            (AgglomerativeAlgorithm::CLINK, Complete) => true,
+            (AgglomerativeAlgorithm::NNChain, Centroid) | (AgglomerativeAlgorithm::NNChain, Median) => false,
+            (AgglomerativeAlgorithm::NNChain, _) => true,
+            _ => false
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Connectivity {
+    /// Every point connected to its k nearest neighbors, found with a kd-tree
+    KNearestNeighbors(usize),
+    /// Neighbors of every point, made symmetric
+    Graph(Vec<Vec<usize>>)
+}
+
+/// A row of the linkage matrix, clusters numbered as in SciPy: the points first, then the cluster formed by every merge
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Merge {
+    pub first: usize,
+    pub second: usize,
+    pub distance: f64,
+    pub size: usize
 }
 
 pub struct Agglomerative {
     assignments: Vec<usize>,
     centroids: Vec<Point>,
+    linkage: Vec<Merge>,
+    no_points: usize,
     iterations: usize,
-    converged: bool
+    converged: bool,
+    link: Link,
+    algorithm: Option<AgglomerativeAlgorithm>,
+    connectivity: Option<Connectivity>
+}
+
+impl Default for Agglomerative {
+    fn default() -> Agglomerative {
+        Agglomerative {
+            assignments: vec![],
+            centroids: vec![],
+            linkage: vec![],
+            no_points: 0,
+            iterations: 0,
+            converged: false,
+            link: Ward,
+            algorithm: None,
+            connectivity: None
+        }
+    }
 }
 
 impl Agglomerative {
-    pub fn run(points: &[Point], no_clusters: usize, link_criterion: &Link) -> Self {
-        let mut clusters: Vec<Cluster> = match *link_criterion {
-            Single | Complete | Average =>
-                (0..points.len()).map(|p| {
-                    Cluster {
-                        points: vec![p],
-                        centroid: vec![]
-                    }
-                }).collect(),
-            Centroid =>
-                points.iter().enumerate().map(|(index, p)| {
-                    Cluster {
-                        points: vec![index],
-                        centroid: p.coordinates().to_vec()
-                    }
-                }).collect(),
-        };
+    pub fn new() -> Self {
+        Agglomerative::default()
+    }
 
-        let mut i = 0;
+    /// Builds the whole dendrogram, then cuts it into `no_clusters` clusters
+    pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
+        let weights = vec![1; points.len()];
+        self.run_weighted(points, &weights, no_clusters)
+    }
 
-        while clusters.len() > no_clusters {
-            clusters = Self::merge_clusters(points, clusters, link_criterion);
-            i += 1;
+    /// As `run`, every point standing for `weights` points, such as the count of a summarized subcluster.
+    /// The weights, at least 1, are the initial cluster sizes of average, centroid and Ward linkage, and count in the sizes of the linkage.
+    pub fn run_weighted(self, points: &[Point], weights: &[usize], no_clusters: usize) -> Self {
+        if weights.len() != points.len() || weights.contains(&0) {
+            panic!("Expected a weight of at least 1 for each of the {} points", points.len())
         }
 
+        let link = self.link;
+        let default = match link {
+            Single => AgglomerativeAlgorithm::SLINK,
+            Centroid | Median => AgglomerativeAlgorithm::Generic,
+            _ => AgglomerativeAlgorithm::NNChain
+        };
+
+        let algorithm = match self.algorithm {
+            Some(algorithm) if algorithm.computes(link) => algorithm,
+            _ => default
+        };
+
+        let linkage = match algorithm {
+            _ if self.connectivity.is_some() => Self::generic(points, weights, link, self.connectivity.as_ref().map(|c| Self::adjacency(points, c))),
+            AgglomerativeAlgorithm::Generic => Self::generic(points, weights, link, None),
+            AgglomerativeAlgorithm::SLINK => Self::slink(points, weights),
+            AgglomerativeAlgorithm::CLINK => Self::clink(points, weights),
+            AgglomerativeAlgorithm::NNChain => match link {
+                Ward => Self::ward_chain(points, weights),
+                _ => Self::nn_chain(points, weights, link)
+            }
+        };
+
+        let output = Agglomerative { linkage, no_points: points.len(), .. self };
+        let assignments = output.cut_at_k(no_clusters);
+        let centroids = Self::centroids_of(points, weights, &assignments);
+
         Agglomerative {
-            assignments: vec![],
-            centroids: vec![],
-            iterations: i,
-            converged: true
+            assignments,
+            centroids,
+            iterations: points.len().saturating_sub(no_clusters.max(1)),
+            converged: true,
+            .. output
+        }
+    }
+
+    fn distances(points: &[Point], link: Link) -> Vec<Vec<f64>> {
+        points.par_iter().map(|p| {
+            points.iter().map(|q| Self::distance(p, q, link)).collect()
+        }).collect()
+    }
+
+    #[inline]
+    fn distance(p: &Point, q: &Point, link: Link) -> f64 {
+        match link.is_squared() {
+            true => SquaredEuclidean::distance(p.coordinates(), q.coordinates()),
+            false => Euclidean::distance(p.coordinates(), q.coordinates())
         }
     }
 
-    fn merge_clusters(points: &[Point], clusters: Vec<Cluster>, link_criterion: &Link) -> Vec<Cluster> {
-        match *link_criterion {
-            Single => Self::merge_by_single_link(points, clusters),
-            Complete => Self::merge_by_complete_link(points, clusters),
-            Average => Self::merge_by_average_link(points, clusters),
-            Centroid => Self::merge_by_centroid_link(clusters)
+    #[inline]
+    fn height(distance: f64, link: Link) -> f64 {
+        match link.is_squared() {
+            true => distance.max(0.0).sqrt(),
+            false => distance
         }
     }
 
-    fn merge_by_average_link(points: &[Point], mut clusters: Vec<Cluster>) -> Vec<Cluster> {
-        let ((closest1, closest2), _) = match clusters.par_iter().enumerate().map(|(index_c1, cluster1)| {
-            match clusters.iter().skip(index_c1 + 1).enumerate().map(|(index_c2, cluster2)| {
-                let avg_distance = cluster1.points.iter().map(|point_c1| {
-                    cluster2.points.iter().map(|point_c2| {
-                        SquaredEuclidean::distance(points[*point_c1].coordinates(), points[*point_c2].coordinates())
-                    }).sum::<f64>()
-                }).sum::<f64>() / ((cluster1.points.len() + cluster2.points.len()) as f64);
+    // With a connectivity graph, only clusters adjacent in it are merge candidates
+    fn generic(points: &[Point], weights: &[usize], link: Link, mut adjacency: Option<Vec<Vec<usize>>>) -> Vec<Merge> {
+        let n = points.len();
+        let mut distances = Self::distances(points, link);
+
+        // Size of the cluster in every slot, zero once merged away, and its number in the linkage
+        let mut sizes: Vec<usize> = weights.to_vec();
+        let mut ids: Vec<usize> = (0..n).collect();
+        let mut nearest: Vec<(usize, f64)> = (0..n).map(|index_c| Self::nearest(&distances, &sizes, adjacency.as_ref(), index_c)).collect();
+        let mut linkage: Vec<Merge> = Vec::with_capacity(n.saturating_sub(1));
 
-                ((index_c1, index_c2), avg_distance)
-            }).min_by(|&(_, a), &(_, b)| {
-                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        while linkage.len() + 1 < n {
+            let (index_c1, index_c2) = match (0..n).filter(|index_c| sizes[*index_c] > 0).min_by(|a, b| {
+                nearest[*a].1.partial_cmp(&nearest[*b].1).unwrap_or(Ordering::Equal)
             }) {
-                Some(closest) => closest,
-                None => ((usize::max_value(), usize::max_value()), f64::INFINITY)
+                Some(index_c) => (index_c, nearest[index_c].0),
+                None => panic!("Expected at least two clusters, found none")
+            };
+
+            // The components of a disconnected graph are merged without any constraint
+            if index_c2 == usize::max_value() {
+                adjacency = None;
+                for index_k in (0..n).filter(|index_k| sizes[*index_k] > 0) {
+                    nearest[index_k] = Self::nearest(&distances, &sizes, None, index_k);
+                }
+
+                continue;
             }
-        }).min_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!("Expected at least two clusters, found none")
-        };
 
-        {
-            let cluster2 = clusters.remove(closest2);
-            let cluster1 = clusters.get_mut(closest1).unwrap();
-            cluster1.points.extend(cluster2.points);
+            let d_12 = distances[index_c1][index_c2];
+            Self::merge_rows(&mut distances, &mut sizes, index_c1, index_c2, link);
+
+            if let Some(ref mut adjacency) = adjacency {
+                let neighbors = adjacency[index_c2].split_off(0);
+                for index_k in neighbors.iter() {
+                    for neighbor in adjacency[*index_k].iter_mut().filter(|neighbor| **neighbor == index_c2) {
+                        *neighbor = index_c1;
+                    }
+                    adjacency[*index_k].sort();
+                    adjacency[*index_k].dedup();
+                }
+
+                adjacency[index_c1].extend(neighbors);
+                adjacency[index_c1].retain(|index_k| *index_k != index_c1 && *index_k != index_c2);
+                adjacency[index_c1].sort();
+                adjacency[index_c1].dedup();
+            }
+
+            linkage.push(Merge {
+                first: ids[index_c1].min(ids[index_c2]),
+                second: ids[index_c1].max(ids[index_c2]),
+                distance: Self::height(d_12, link),
+                size: sizes[index_c1]
+            });
+
+            ids[index_c1] = n + linkage.len() - 1;
+
+            for index_k in (0..n).filter(|index_k| sizes[*index_k] > 0) {
+                let adjacent = adjacency.as_ref().map_or(true, |adjacency| adjacency[index_k].contains(&index_c1));
+
+                if index_k == index_c1 || nearest[index_k].0 == index_c1 || nearest[index_k].0 == index_c2 {
+                    nearest[index_k] = Self::nearest(&distances, &sizes, adjacency.as_ref(), index_k);
+                } else if adjacent && distances[index_k][index_c1] < nearest[index_k].1 {
+                    nearest[index_k] = (index_c1, distances[index_k][index_c1]);
+                }
+            }
         }
 
-        clusters
+        linkage
     }
 
-    fn merge_by_centroid_link(mut clusters: Vec<Cluster>) -> Vec<Cluster> {
-        let ((closest1, closest2), _) = match clusters.par_iter().enumerate().map(|(index_c1, cluster1)| {
-            match clusters.iter().skip(index_c1 + 1).enumerate().map(|(index_c2, cluster2)| {
-                ((index_c1, index_c2), SquaredEuclidean::distance(&cluster1.centroid, &cluster2.centroid))
-            }).min_by(|&(_, a), &(_, b)| {
-                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-            }) {
-                Some(closest) => closest,
-                None => ((usize::max_value(), usize::max_value()), f64::INFINITY)
+    // Symmetric neighbor lists of every point
+    fn adjacency(points: &[Point], connectivity: &Connectivity) -> Vec<Vec<usize>> {
+        let mut adjacency: Vec<Vec<usize>> = match *connectivity {
+            Connectivity::KNearestNeighbors(k) => {
+                let mut references: Vec<&Point> = points.iter().collect();
+                let tree = KDTree::new(references.as_mut_slice());
+
+                points.par_iter().enumerate().map(|(index_p, p)| {
+                    tree.nearest::<Euclidean>(p.coordinates(), k + 1).into_iter().map(|(index_q, _)| index_q).filter(|index_q| *index_q != index_p).collect()
+                }).collect()
+            },
+            Connectivity::Graph(ref graph) => {
+                if let Some(index_q) = graph.iter().flat_map(|neighbors| neighbors.iter()).find(|index_q| **index_q >= points.len()) {
+                    panic!("Expected neighbors among the {} points, found neighbor {}", points.len(), index_q)
+                }
+
+                graph.clone()
             }
-        }).min_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!("Expected at least two clusters, found none")
         };
 
-        {
-            let cluster2 = clusters.remove(closest2);
-            let cluster1 = clusters.get_mut(closest1).unwrap();
-            cluster1.points.extend(cluster2.points);
-            cluster1.centroid = Statistics::mean(&[&cluster1.centroid, &cluster2.centroid]);
+        adjacency.resize(points.len(), vec![]);
+        for index_p in 0..points.len() {
+            for index_q in adjacency[index_p].clone() {
+                adjacency[index_q].push(index_p);
+            }
+        }
+
+        for (index_p, neighbors) in adjacency.iter_mut().enumerate() {
+            neighbors.retain(|index_q| *index_q != index_p);
+            neighbors.sort();
+            neighbors.dedup();
         }
 
-        clusters
+        adjacency
     }
 
-    fn merge_by_complete_link(points: &[Point], mut clusters: Vec<Cluster>) -> Vec<Cluster> {
-        let ((closest1, closest2), _) = match clusters.par_iter().enumerate().map(|(index_c1, cluster1)| {
-            match clusters.iter().skip(index_c1 + 1).enumerate().map(|(index_c2, cluster2)| {
-                let max_distance = match cluster1.points.iter().map(|point_c1| {
-                    match cluster2.points.iter().map(|point_c2| {
-                        SquaredEuclidean::distance(points[*point_c1].coordinates(), points[*point_c2].coordinates())
-                    }).max_by(|a, b| {
-                        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-                    }) {
-                        Some(closest) => closest,
-                        None => f64::NEG_INFINITY
-                    }
-                }).max_by(|a, b| {
-                    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-                }) {
-                    Some(closest) => closest,
-                    None => f64::NEG_INFINITY
-                };
-
-                ((index_c1, index_c2), max_distance)
-            }).max_by(|&(_, a), &(_, b)| {
-                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-            }) {
-                Some(closest) => closest,
-                None => ((usize::max_value(), usize::max_value()), f64::NEG_INFINITY)
+    // The merged cluster takes the place of the first, so only its row of the matrix changes
+    fn merge_rows(distances: &mut [Vec<f64>], sizes: &mut [usize], index_c1: usize, index_c2: usize, link: Link) {
+        let (n_1, n_2) = (sizes[index_c1], sizes[index_c2]);
+        let d_12 = distances[index_c1][index_c2];
+
+        for index_k in (0..sizes.len()).filter(|index_k| *index_k != index_c1 && *index_k != index_c2 && sizes[*index_k] > 0) {
+            let distance = link.update(distances[index_k][index_c1], distances[index_k][index_c2], d_12, n_1, n_2, sizes[index_k]);
+
+            distances[index_k][index_c1] = distance;
+            distances[index_c1][index_k] = distance;
+        }
+
+        sizes[index_c1] = n_1 + n_2;
+        sizes[index_c2] = 0;
+    }
+
+    // Follows nearest neighbors until two clusters are each other's, which reducible linkages may merge right away
+    fn nn_chain(points: &[Point], weights: &[usize], link: Link) -> Vec<Merge> {
+        let n = points.len();
+        let mut state = (Self::distances(points, link), weights.to_vec());
+
+        let merges = Self::chain(n, &mut state, |&(ref distances, ref sizes), index_c, previous| {
+            Self::nearest_preferring(sizes, index_c, previous, |index_k| distances[index_c][index_k])
+        }, |&mut (ref mut distances, ref mut sizes), index_c1, index_c2| {
+            let d_12 = distances[index_c1][index_c2];
+            Self::merge_rows(distances, sizes, index_c1, index_c2, link);
+            Self::height(d_12, link)
+        });
+
+        Self::sorted_linkage(weights, merges)
+    }
+
+    // Ward distances follow from the centroids and sizes alone, so no matrix is needed
+    fn ward_chain(points: &[Point], weights: &[usize]) -> Vec<Merge> {
+        let n = points.len();
+        let centroids: Vec<Vec<f64>> = points.iter().map(|p| p.coordinates().to_vec()).collect();
+        let mut state = (centroids, weights.to_vec());
+
+        fn ward(c1: &[f64], c2: &[f64], n_1: usize, n_2: usize) -> f64 {
+            2.0 * (n_1 * n_2) as f64 / (n_1 + n_2) as f64 * SquaredEuclidean::distance(c1, c2)
+        }
+
+        let merges = Self::chain(n, &mut state, |&(ref centroids, ref sizes), index_c, previous| {
+            Self::nearest_preferring(sizes, index_c, previous, |index_k| ward(&centroids[index_c], &centroids[index_k], sizes[index_c], sizes[index_k]))
+        }, |&mut (ref mut centroids, ref mut sizes), index_c1, index_c2| {
+            let (n_1, n_2) = (sizes[index_c1], sizes[index_c2]);
+            let distance = ward(&centroids[index_c1], &centroids[index_c2], n_1, n_2);
+
+            let merged: Vec<f64> = centroids[index_c1].iter().zip(centroids[index_c2].iter()).map(|(a, b)| {
+                (a * n_1 as f64 + b * n_2 as f64) / (n_1 + n_2) as f64
+            }).collect();
+            centroids[index_c1] = merged;
+
+            sizes[index_c1] = n_1 + n_2;
+            sizes[index_c2] = 0;
+            distance.sqrt()
+        });
+
+        Self::sorted_linkage(weights, merges)
+    }
+
+    // The chain itself, returning the merges as pairs of slots in the order they happened. The merged cluster keeps the first slot.
+    fn chain<S, N, M>(n: usize, state: &mut S, nearest: N, merge: M) -> Vec<(usize, usize, f64)>
+        where N: Fn(&S, usize, Option<usize>) -> usize,
+              M: Fn(&mut S, usize, usize) -> f64 {
+        let mut merges = Vec::with_capacity(n.saturating_sub(1));
+        let mut chain: Vec<usize> = Vec::with_capacity(n);
+        let mut alive = vec![true; n];
+
+        while merges.len() + 1 < n {
+            if chain.is_empty() {
+                chain.push((0..n).find(|index_c| alive[*index_c]).unwrap());
             }
-        }).max_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!("Expected at least two clusters, found none")
-        };
 
-        {
-            let cluster2 = clusters.remove(closest2);
-            let cluster1 = clusters.get_mut(closest1).unwrap();
-            cluster1.points.extend(cluster2.points);
+            loop {
+                let index_a = chain[chain.len() - 1];
+                let previous = if chain.len() > 1 { Some(chain[chain.len() - 2]) } else { None };
+                let index_b = nearest(state, index_a, previous);
+
+                if Some(index_b) == previous {
+                    break;
+                }
+
+                chain.push(index_b);
+            }
+
+            let (index_b, index_a) = (chain.pop().unwrap(), chain.pop().unwrap());
+            let (index_c1, index_c2) = (index_a.min(index_b), index_a.max(index_b));
+
+            merges.push((index_c1, index_c2, merge(state, index_c1, index_c2)));
+            alive[index_c2] = false;
+        }
+
+        merges
+    }
+
+    // On ties the previous cluster of the chain wins, otherwise the chain could go around in circles
+    fn nearest_preferring<F: Fn(usize) -> f64>(sizes: &[usize], index_c: usize, previous: Option<usize>, distance: F) -> usize {
+        let start = previous.map(|index_k| (index_k, distance(index_k))).unwrap_or((usize::max_value(), f64::INFINITY));
+
+        (0..sizes.len()).filter(|index_k| *index_k != index_c && sizes[*index_k] > 0).fold(start, |best, index_k| {
+            let d = distance(index_k);
+            if d < best.1 || best.0 == usize::max_value() { (index_k, d) } else { best }
+        }).0
+    }
+
+    fn slink(points: &[Point], weights: &[usize]) -> Vec<Merge> {
+        let n = points.len();
+        let mut pi: Vec<usize> = vec![0; n];
+        let mut lambda: Vec<f64> = vec![f64::INFINITY; n];
+        let mut m: Vec<f64> = vec![0.0; n];
+
+        for i in 0..n {
+            pi[i] = i;
+            lambda[i] = f64::INFINITY;
+
+            for j in 0..i {
+                m[j] = Euclidean::distance(points[i].coordinates(), points[j].coordinates());
+            }
+
+            for j in 0..i {
+                if lambda[j] >= m[j] {
+                    m[pi[j]] = m[pi[j]].min(lambda[j]);
+                    lambda[j] = m[j];
+                    pi[j] = i;
+                } else {
+                    m[pi[j]] = m[pi[j]].min(m[j]);
+                }
+            }
+
+            for j in 0..i {
+                if lambda[j] >= lambda[pi[j]] {
+                    pi[j] = i;
+                }
+            }
         }
 
-        clusters
+        Self::sorted_linkage(weights, (0..n).filter(|i| pi[*i] != *i).map(|i| (i, pi[i], lambda[i])).collect())
     }
 
-    fn merge_by_single_link(points: &[Point], mut clusters: Vec<Cluster>) -> Vec<Cluster> {
-        let ((closest1, closest2), _) = match clusters.par_iter().enumerate().map(|(index_c1, cluster1)| {
-            match clusters.iter().skip(index_c1 + 1).enumerate().map(|(index_c2, cluster2)| {
-                let max_distance = match cluster1.points.iter().map(|point_c1| {
-                    match cluster2.points.iter().map(|point_c2| {
-                        SquaredEuclidean::distance(points[*point_c1].coordinates(), points[*point_c2].coordinates())
-                    }).min_by(|a, b| {
-                        a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-                    }) {
-                        Some(closest) => closest,
-                        None => f64::INFINITY
+    fn clink(points: &[Point], weights: &[usize]) -> Vec<Merge> {
+        let n = points.len();
+        let mut pi: Vec<usize> = vec![0; n];
+        let mut lambda: Vec<f64> = vec![f64::INFINITY; n];
+        let mut m: Vec<f64> = vec![0.0; n];
+
+        for i in 0..n {
+            pi[i] = i;
+            lambda[i] = f64::INFINITY;
+            if i == 0 {
+                continue;
+            }
+
+            for j in 0..i {
+                m[j] = Euclidean::distance(points[i].coordinates(), points[j].coordinates());
+            }
+
+            for j in 0..i {
+                if lambda[j] < m[j] {
+                    m[pi[j]] = m[pi[j]].max(m[j]);
+                    m[j] = f64::INFINITY;
+                }
+            }
+
+            let mut a = i - 1;
+            for j in (0..i).rev() {
+                if lambda[j] >= m[pi[j]] {
+                    if m[j] < m[a] {
+                        a = j;
                     }
-                }).min_by(|a, b| {
-                    a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-                }) {
-                    Some(closest) => closest,
-                    None => f64::INFINITY
-                };
-
-                ((index_c1, index_c2), max_distance)
-            }).min_by(|&(_, a), &(_, b)| {
-                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-            }) {
-                Some(closest) => closest,
-                None => ((usize::max_value(), usize::max_value()), f64::INFINITY)
+                } else {
+                    m[j] = f64::INFINITY;
+                }
             }
-        }).min_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!("Expected at least two clusters, found none")
+
+            let mut b = pi[a];
+            let mut c = lambda[a];
+            pi[a] = i;
+            lambda[a] = m[a];
+
+            if a < i - 1 {
+                while b < i - 1 {
+                    let (d, e) = (pi[b], lambda[b]);
+                    pi[b] = i;
+                    lambda[b] = c;
+                    b = d;
+                    c = e;
+                }
+
+                if b == i - 1 {
+                    pi[b] = i;
+                    lambda[b] = c;
+                }
+            }
+
+            for j in 0..i {
+                if pi[pi[j]] == i && lambda[j] >= lambda[pi[j]] {
+                    pi[j] = i;
+                }
+            }
+        }
+
+        Self::sorted_linkage(weights, (0..n).filter(|i| pi[*i] != *i).map(|i| (i, pi[i], lambda[i])).collect())
+    }
+
+    // Linkage from merges given by any point of both clusters, replayed in order of distance
+    fn sorted_linkage(weights: &[usize], mut merges: Vec<(usize, usize, f64)>) -> Vec<Merge> {
+        merges.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal));
+
+        let n = weights.len();
+        let mut parents: Vec<usize> = (0..n).collect();
+        let mut ids: Vec<usize> = (0..n).collect();
+        let mut sizes: Vec<usize> = weights.to_vec();
+
+        fn root(parents: &mut [usize], mut c: usize) -> usize {
+            while parents[c] != c {
+                parents[c] = parents[parents[c]];
+                c = parents[c];
+            }
+            c
+        }
+
+        merges.into_iter().enumerate().map(|(index_m, (a, b, distance))| {
+            let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+            let merge = Merge {
+                first: ids[root_a].min(ids[root_b]),
+                second: ids[root_a].max(ids[root_b]),
+                distance,
+                size: sizes[root_a] + sizes[root_b]
+            };
+
+            parents[root_b] = root_a;
+            sizes[root_a] += sizes[root_b];
+            ids[root_a] = n + index_m;
+            merge
+        }).collect()
+    }
+
+    // Closest other cluster still alive
+    fn nearest(distances: &[Vec<f64>], sizes: &[usize], adjacency: Option<&Vec<Vec<usize>>>, index_c: usize) -> (usize, f64) {
+        let candidates: Vec<usize> = match adjacency {
+            Some(adjacency) => adjacency[index_c].clone(),
+            None => (0..sizes.len()).collect()
         };
 
-        {
-            let cluster2 = clusters.remove(closest2);
-            let cluster1 = clusters.get_mut(closest1).unwrap();
-            cluster1.points.extend(cluster2.points);
+        candidates.into_iter().filter(|index_k| *index_k != index_c && sizes[*index_k] > 0).map(|index_k| (index_k, distances[index_c][index_k])).min_by(|&(_, a), &(_, b)| {
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }).unwrap_or((usize::max_value(), f64::INFINITY))
+    }
+
+    fn centroids_of(points: &[Point], weights: &[usize], assignments: &[usize]) -> Vec<Point> {
+        let no_clusters = assignments.iter().map(|a| a + 1).max().unwrap_or(0);
+        let dimension = points.first().map_or(0, |p| p.coordinates().len());
+
+        let sums = points.iter().zip(weights.iter()).zip(assignments.iter()).fold(vec![(vec![0.0; dimension], 0.0); no_clusters], |mut sums, ((p, w), a)| {
+            for (s, x) in sums[*a].0.iter_mut().zip(p.coordinates().iter()) {
+                *s += *w as f64 * x;
+            }
+            sums[*a].1 += *w as f64;
+            sums
+        });
+
+        sums.into_iter().map(|(sum, weight)| Point::new(sum.into_iter().map(|s| s / weight).collect())).collect()
+    }
+
+    /// Flat clustering with `no_clusters` clusters, from the same dendrogram
+    pub fn cut_at_k(&self, no_clusters: usize) -> Vec<usize> {
+        let no_merges = self.no_points.saturating_sub(no_clusters.max(1));
+        let merged: Vec<bool> = (0..self.linkage.len()).map(|index_m| index_m < no_merges).collect();
+
+        self.labels(&merged)
+    }
+
+    /// Flat clustering merging every cluster formed at a distance of at most `distance`
+    pub fn cut_at_distance(&self, distance: f64) -> Vec<usize> {
+        // Centroid and median linkage can merge below the height of an earlier merge, so a cluster is only formed if its whole subtree is
+        let heights: Vec<f64> = self.linkage.iter().map(|m| m.distance).collect();
+        let merged: Vec<bool> = self.subtree_max(&heights).iter().map(|h| *h <= distance).collect();
+
+        self.labels(&merged)
+    }
+
+    /// Flat clustering merging every cluster whose merges all have an inconsistency coefficient of at most `threshold`
+    pub fn cut_at_inconsistency(&self, threshold: f64, depth: usize) -> Vec<usize> {
+        let coefficients: Vec<f64> = self.inconsistency(depth).iter().map(|row| row[3]).collect();
+        let merged: Vec<bool> = self.subtree_max(&coefficients).iter().map(|c| *c <= threshold).collect();
+
+        self.labels(&merged)
+    }
+
+    // Largest value of every merge and the merges below it
+    fn subtree_max(&self, values: &[f64]) -> Vec<f64> {
+        let n = self.linkage.len() + 1;
+        let mut maxima: Vec<f64> = Vec::with_capacity(self.linkage.len());
+
+        for (merge, value) in self.linkage.iter().zip(values.iter()) {
+            let maximum = [merge.first, merge.second].iter().filter(|c| **c >= n).map(|c| maxima[*c - n]).fold(*value, f64::max);
+            maxima.push(maximum);
+        }
+
+        maxima
+    }
+
+    /// Height of the merge first joining every pair of points
+    pub fn cophenetic_distances(&self) -> Vec<Vec<f64>> {
+        let n = self.no_points;
+        let mut members: Vec<Vec<usize>> = (0..n).map(|index_p| vec![index_p]).collect();
+        let mut distances = vec![vec![0.0; n]; n];
+
+        for merge in self.linkage.iter() {
+            let first = members[merge.first].split_off(0);
+            let second = members[merge.second].split_off(0);
+
+            for index_p in first.iter() {
+                for index_q in second.iter() {
+                    distances[*index_p][*index_q] = merge.distance;
+                    distances[*index_q][*index_p] = merge.distance;
+                }
+            }
+
+            members.push(first.into_iter().chain(second.into_iter()).collect());
+        }
+
+        distances
+    }
+
+    /// Pearson correlation between the distances of the points and their cophenetic distances, closer to 1 when the dendrogram preserves the distances better
+    pub fn cophenetic_correlation<D: Distance>(&self, points: &[Point]) -> f64 {
+        let cophenetic = self.cophenetic_distances();
+        let pairs: Vec<(f64, f64)> = (0..points.len()).flat_map(|index_p| (index_p + 1..points.len()).map(move |index_q| (index_p, index_q))).map(|(index_p, index_q)| {
+            (D::distance(points[index_p].coordinates(), points[index_q].coordinates()), cophenetic[index_p][index_q])
+        }).collect();
+
+        let no_pairs = pairs.len() as f64;
+        let (mean_x, mean_y) = (pairs.iter().map(|p| p.0).sum::<f64>() / no_pairs, pairs.iter().map(|p| p.1).sum::<f64>() / no_pairs);
+        let (covariance, variance_x, variance_y) = pairs.iter().fold((0.0, 0.0, 0.0), |(c, vx, vy), &(x, y)| {
+            (c + (x - mean_x) * (y - mean_y), vx + (x - mean_x) * (x - mean_x), vy + (y - mean_y) * (y - mean_y))
+        });
+
+        covariance / (variance_x * variance_y).sqrt()
+    }
+
+    /// For every merge, as in SciPy: the mean and standard deviation of the heights of the merges up to `depth` levels down from it,
+    /// their number, and how many standard deviations the merge lies above the mean
+    pub fn inconsistency(&self, depth: usize) -> Vec<[f64; 4]> {
+        let n = self.linkage.len() + 1;
+
+        (0..self.linkage.len()).map(|index_m| {
+            let mut heights = vec![];
+            let mut stack = vec![(index_m, 1)];
+
+            while let Some((index_l, level)) = stack.pop() {
+                let merge = &self.linkage[index_l];
+                heights.push(merge.distance);
+
+                if level < depth {
+                    stack.extend([merge.first, merge.second].iter().filter(|c| **c >= n).map(|c| (*c - n, level + 1)));
+                }
+            }
+
+            let count = heights.len() as f64;
+            let mean = heights.iter().sum::<f64>() / count;
+            let deviation = match heights.len() > 1 {
+                true => (heights.iter().map(|h| (h - mean) * (h - mean)).sum::<f64>() / (count - 1.0)).sqrt(),
+                false => 0.0
+            };
+            let coefficient = if deviation > 0.0 { (self.linkage[index_m].distance - mean) / deviation } else { 0.0 };
+
+            [mean, deviation, count, coefficient]
+        }).collect()
+    }
+
+    // Labels of the clusters left after applying the chosen merges, numbered by their first point
+    fn labels(&self, merged: &[bool]) -> Vec<usize> {
+        let n = self.no_points;
+        let mut parents: Vec<usize> = (0..n + self.linkage.len()).collect();
+
+        for (index_m, merge) in self.linkage.iter().enumerate().filter(|&(index_m, _)| merged[index_m]) {
+            parents[merge.first] = n + index_m;
+            parents[merge.second] = n + index_m;
         }
 
-        clusters
+        let root = |mut c: usize| {
+            while parents[c] != c {
+                c = parents[c];
+            }
+            c
+        };
+
+        let mut labels: Vec<Option<usize>> = vec![None; n + self.linkage.len()];
+        let mut no_labels = 0;
+
+        (0..n).map(|index_p| {
+            let r = root(index_p);
+            *labels[r].get_or_insert_with(|| {
+                no_labels += 1;
+                no_labels - 1
+            })
+        }).collect()
     }
 
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
     pub fn centroids(&self) -> &[Point] {
         &self.centroids
     }
+
+    /// Number of merges leading to the flat clustering of `run`
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    pub fn converged(&self) -> bool { self.converged }
+
+    /// The n - 1 merges in the order they happened
+    pub fn linkage(&self) -> &[Merge] { &self.linkage }
+
+    /// The linkage as rows of [first, second, distance, size], the layout of SciPy's linkage matrix
+    pub fn linkage_matrix(&self) -> Vec<[f64; 4]> {
+        self.linkage.iter().map(|m| [m.first as f64, m.second as f64, m.distance, m.size as f64]).collect()
+    }
+
+    pub fn dendrogram(&self) -> Dendrogram {
+        Dendrogram::new(&self.linkage, self.no_points)
+    }
+
+    pub fn set_link(self, link: Link) -> Self {
+        Agglomerative { link, .. self }
+    }
+
+    /// Algorithm building the dendrogram, chosen from the link if not set or unable to compute it.
+    /// Ignored with a connectivity, which always uses the generic algorithm.
+    ///
+    /// Only single linkage (SLINK), Ward linkage (nearest-neighbor chain over the centroids) and CLINK run in O(n) memory.
+    /// The defaults for average, complete, weighted, centroid and median linkage keep an n × n distance matrix,
+    /// about 3.2 GB for 20000 points. CLINK is the only O(n) memory option for complete linkage, at the price of
+    /// an approximate dendrogram.
+    pub fn set_algorithm(self, algorithm: Option<AgglomerativeAlgorithm>) -> Self {
+        Agglomerative { algorithm, .. self }
+    }
+
+    /// Only merges clusters connected in the graph, always with the generic algorithm. Disconnected components are merged last, without constraint.
+    /// The distances between all clusters are still kept for those last merges, so memory stays O(n²) however sparse the graph.
+    pub fn set_connectivity(self, connectivity: Option<Connectivity>) -> Self {
+        Agglomerative { connectivity, .. self }
+    }
 }
 
 #[cfg(test)]
@@ -224,6 +758,220 @@ mod tests {
     use rand::Rng;
     use time;
 
+    #[test]
+    fn can_run_every_link() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = [(0.0, 0.0), (5.0, 5.0), (-5.0, 5.0)].iter().flat_map(|&(x, y)| {
+            (0..30).map(|_| Point::new(vec![x + rng.next_f64(), y + rng.next_f64()])).collect::<Vec<Point>>()
+        }).collect();
+
+        for link in [Single, Complete, Average, Weighted, Centroid, Median, Ward].iter() {
+            let output = Agglomerative::new().set_link(*link).run(&points, 3);
+
+            assert_eq!(89, output.linkage().len());
+            assert_eq!(3, output.centroids().len());
+            for blob in 0..3 {
+                assert!(output.assignments()[blob * 30..(blob + 1) * 30].iter().all(|a| *a == output.assignments()[blob * 30]));
+            }
+        }
+    }
+
+    #[test]
+    fn lance_williams_matches_definitions() {
+        let points: Vec<Point> = [0.0, 1.0, 3.0, 7.0, 8.0].iter().map(|x| Point::new(vec![*x])).collect();
+
+        // Distances of {7, 8} to {0, 1} and {3} once these merge
+        assert_eq!(Complete.update(8.0, 5.0, 3.0, 2, 1, 2), 8.0);
+        assert!((Average.update(7.0, 4.5, 2.5, 2, 1, 2) - 37.0 / 6.0).abs() < 1e-9);
+        assert_eq!(Weighted.update(7.0, 4.5, 2.5, 2, 1, 2), 5.75);
+
+        // Ward distance of {7, 8} to {0, 1, 3}: 2 * 3 / 5 * (7.5 - 4/3)^2
+        let ward = Ward.update(2.0 * 2.0 / 4.0 * 49.0, 2.0 / 3.0 * 4.5 * 4.5, 2.0 / 3.0 * 2.5 * 2.5, 2, 1, 2);
+        assert!((ward - 6.0 / 5.0 * (7.5f64 - 4.0 / 3.0).powi(2)).abs() < 1e-9);
+
+        assert_eq!(&[0, 0, 0, 1, 1], Agglomerative::new().set_link(Ward).run(&points, 2).assignments());
+        assert_eq!(&[0, 0, 1, 2, 2], Agglomerative::new().set_link(Single).run(&points, 3).assignments());
+    }
+
+    #[test]
+    fn linkage_matches_scipy() {
+        let points: Vec<Point> = [0.0, 1.0, 3.0, 7.0, 8.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let output = Agglomerative::new().set_link(Average).run(&points, 2);
+
+        // scipy.cluster.hierarchy.linkage([[0], [1], [3], [7], [8]], 'average')
+        let expected = vec![
+            [0.0, 1.0, 1.0, 2.0],
+            [3.0, 4.0, 1.0, 2.0],
+            [2.0, 5.0, 2.5, 3.0],
+            [6.0, 7.0, 37.0 / 6.0, 5.0]
+        ];
+
+        for (row, expected_row) in output.linkage_matrix().iter().zip(expected.iter()) {
+            assert!(row.iter().zip(expected_row.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+        }
+
+        assert_eq!(&[Point::new(vec![4.0 / 3.0]), Point::new(vec![7.5])], output.centroids());
+    }
+
+    #[test]
+    fn fast_algorithms_match_generic() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..200).map(|_| Point::new(vec![rng.next_f64(), rng.next_f64()])).collect();
+
+        for link in [Single, Complete, Average, Weighted, Ward].iter() {
+            let generic = Agglomerative::new().set_link(*link).set_algorithm(Some(AgglomerativeAlgorithm::Generic)).run(&points, 5);
+            let fast = Agglomerative::new().set_link(*link).run(&points, 5);
+
+            assert!(generic.linkage().iter().zip(fast.linkage().iter()).all(|(a, b)| (a.distance - b.distance).abs() < 1e-9));
+            assert!(generic.linkage().iter().zip(fast.linkage().iter()).all(|(a, b)| a.size == b.size));
+        }
+
+        let nn_chain = Agglomerative::new().set_link(Single).set_algorithm(Some(AgglomerativeAlgorithm::NNChain)).run(&points, 5);
+        let slink = Agglomerative::new().set_link(Single).run(&points, 5);
+        assert_eq!(slink.linkage(), nn_chain.linkage());
+
+        // Algorithms unable to compute the link give way to its default
+        let ward = Agglomerative::new().set_link(Ward).run(&points, 5);
+        let slink = Agglomerative::new().set_link(Ward).set_algorithm(Some(AgglomerativeAlgorithm::SLINK)).run(&points, 5);
+        let clink = Agglomerative::new().set_link(Ward).set_algorithm(Some(AgglomerativeAlgorithm::CLINK)).run(&points, 5);
+        let nn_chain = Agglomerative::new().set_link(Centroid).set_algorithm(Some(AgglomerativeAlgorithm::NNChain)).run(&points, 5);
+        assert_eq!(ward.linkage(), slink.linkage());
+        assert_eq!(ward.linkage(), clink.linkage());
+        assert_eq!(Agglomerative::new().set_link(Centroid).run(&points, 5).linkage(), nn_chain.linkage());
+    }
+
+    #[test]
+    fn weights_count_as_repeated_points() {
+        let points: Vec<Point> = [0.0, 3.0, 7.0, 12.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let repeated: Vec<Point> = [0.0, 0.0, 0.0, 3.0, 7.0, 12.0, 12.0].iter().map(|x| Point::new(vec![*x])).collect();
+
+        for link in [Average, Centroid, Ward].iter() {
+            let weighted = Agglomerative::new().set_link(*link).run_weighted(&points, &[3, 1, 1, 2], 2);
+            let output = Agglomerative::new().set_link(*link).run(&repeated, 2);
+
+            // The repeated points first merge at height 0, after which both linkages agree
+            assert!(weighted.linkage().iter().zip(output.linkage()[3..].iter()).all(|(a, b)| (a.distance - b.distance).abs() < 1e-9 && a.size == b.size));
+            assert_eq!(output.centroids(), weighted.centroids());
+        }
+    }
+
+    #[test]
+    fn can_run_clink() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..90).map(|_| Point::new(vec![rng.next_f64(), rng.next_f64()])).collect();
+
+        let output = Agglomerative::new().set_link(Complete).set_algorithm(Some(AgglomerativeAlgorithm::CLINK)).run(&points, 3);
+        let complete = Agglomerative::new().set_link(Complete).run(&points, 3);
+        let single = Agglomerative::new().set_link(Single).run(&points, 3);
+
+        assert_eq!(89, output.linkage().len());
+        assert_eq!(90, output.linkage()[88].size);
+        assert!((output.linkage()[88].distance - complete.linkage()[88].distance).abs() < 1e-9);
+        assert!(output.linkage().iter().zip(single.linkage().iter()).all(|(c, s)| c.distance >= s.distance - 1e-9));
+
+        // Inserting the points one at a time, CLINK cannot undo that c joined {a, b} before d came along
+        let points: Vec<Point> = [0.0, 10.0, -13.0, -25.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let output = Agglomerative::new().set_link(Complete).set_algorithm(Some(AgglomerativeAlgorithm::CLINK)).run(&points, 1);
+        let complete = Agglomerative::new().set_link(Complete).run(&points, 1);
+
+        assert_eq!(vec![10.0, 23.0, 35.0], output.linkage().iter().map(|m| m.distance).collect::<Vec<f64>>());
+        assert_eq!(vec![10.0, 12.0, 35.0], complete.linkage().iter().map(|m| m.distance).collect::<Vec<f64>>());
+    }
+
+    #[test]
+    fn can_restrict_merges_to_neighbors() {
+        let mut rng = rand::thread_rng();
+
+        // Two concentric rings, which unconstrained average linkage cuts across
+        let points: Vec<Point> = (0..2).flat_map(|ring| {
+            let radius = 1.0 + 4.0 * ring as f64;
+            (0..100).map(|i| {
+                let angle = 2.0 * f64::consts::PI * (i as f64 + rng.next_f64()) / 100.0;
+                Point::new(vec![radius * angle.cos() + 0.1 * rng.next_f64(), radius * angle.sin() + 0.1 * rng.next_f64()])
+            }).collect::<Vec<Point>>()
+        }).collect();
+
+        let output = Agglomerative::new().set_link(Average).set_connectivity(Some(Connectivity::KNearestNeighbors(5))).run(&points, 2);
+
+        assert_eq!(199, output.linkage().len());
+        assert!(output.assignments()[..100].iter().all(|a| *a == output.assignments()[0]));
+        assert!(output.assignments()[100..].iter().all(|a| *a != output.assignments()[0]));
+    }
+
+    #[test]
+    #[should_panic(expected = "found neighbor 9")]
+    fn rejects_neighbors_out_of_range() {
+        let points: Vec<Point> = (0..6).map(|x| Point::new(vec![x as f64])).collect();
+        let graph = vec![vec![1], vec![2], vec![9]];
+
+        Agglomerative::new().set_connectivity(Some(Connectivity::Graph(graph))).run(&points, 2);
+    }
+
+    #[test]
+    fn can_use_adjacency_list() {
+        let points: Vec<Point> = [0.0, 1.0, 2.0, 10.0, 11.0].iter().map(|x| Point::new(vec![*x])).collect();
+
+        // 1 is only reachable through 4, so it has to wait for 4 to merge with 3
+        let graph = vec![vec![2], vec![4], vec![0], vec![4], vec![]];
+        let output = Agglomerative::new().set_link(Complete).set_connectivity(Some(Connectivity::Graph(graph))).run(&points, 2);
+
+        assert_eq!(vec![0, 1, 0, 1, 1], output.assignments().to_vec());
+        assert_eq!(vec![1.0, 2.0, 10.0, 11.0], output.linkage().iter().map(|m| m.distance).collect::<Vec<f64>>());
+    }
+
+    #[test]
+    fn can_compare_with_cophenetic_distances() {
+        let points: Vec<Point> = [0.0, 1.0, 10.0, 11.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let output = Agglomerative::new().set_link(Complete).run(&points, 2);
+
+        let cophenetic = output.cophenetic_distances();
+        assert_eq!(vec![0.0, 1.0, 11.0, 11.0], cophenetic[0]);
+        assert_eq!(vec![11.0, 11.0, 1.0, 0.0], cophenetic[3]);
+        assert!((output.cophenetic_correlation::<Euclidean>(&points) - 0.9908673886137245).abs() < 1e-9);
+    }
+
+    #[test]
+    fn can_cut_at_inconsistency() {
+        let points: Vec<Point> = [0.0, 1.0, 10.0, 11.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let output = Agglomerative::new().set_link(Complete).run(&points, 2);
+        let inconsistency = output.inconsistency(2);
+
+        assert_eq!([1.0, 0.0, 1.0, 0.0], inconsistency[0]);
+        assert!((inconsistency[2][0] - 13.0 / 3.0).abs() < 1e-9);
+        assert!((inconsistency[2][1] - (100.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert_eq!(3.0, inconsistency[2][2]);
+        assert!((inconsistency[2][3] - 2.0 / 3.0f64.sqrt()).abs() < 1e-9);
+
+        assert_eq!(vec![0, 0, 1, 1], output.cut_at_inconsistency(1.0, 2));
+        assert_eq!(vec![0, 0, 0, 0], output.cut_at_inconsistency(1.2, 2));
+    }
+
+    #[test]
+    fn can_run_without_points() {
+        let output = Agglomerative::new().run(&[], 3);
+
+        assert!(output.assignments().is_empty());
+        assert!(output.centroids().is_empty());
+        assert!(output.cut_at_distance(1.0).is_empty());
+        assert!(output.cophenetic_distances().is_empty());
+        assert_eq!(0, output.iterations());
+    }
+
+    #[test]
+    fn can_cut_dendrogram() {
+        let points: Vec<Point> = [0.0, 1.0, 3.0, 7.0, 8.0].iter().map(|x| Point::new(vec![*x])).collect();
+        let output = Agglomerative::new().set_link(Single).run(&points, 1);
+
+        assert_eq!(vec![0, 0, 0, 0, 0], output.assignments().to_vec());
+        assert_eq!(4, output.iterations());
+        assert!(output.converged());
+        assert_eq!(vec![0, 1, 2, 3, 4], output.cut_at_k(5));
+        assert_eq!(vec![0, 0, 1, 2, 2], output.cut_at_k(3));
+        assert_eq!(vec![0, 0, 1, 2, 2], output.cut_at_distance(1.5));
+        assert_eq!(vec![0, 0, 0, 1, 1], output.cut_at_distance(2.0));
+        assert_eq!(vec![0, 0, 0, 0, 0], output.cut_at_distance(10.0));
+    }
+
     /*#[test]
     fn can_run() {
         let expected: Vec<KMeansCluster> = vec![
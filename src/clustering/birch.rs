@@ -0,0 +1,340 @@
+// http://www.cs.sfu.ca/CourseCentral/459/han/papers/zhang96.pdf
+
+use std::cmp::Ordering;
+use std::f64;
+use point::Point;
+use clustering::kmeans::{KMeans, KMeansInitialization};
+use clustering::agglomerative::{Agglomerative, Link};
+use statistics::distance::{Distance, SquaredEuclidean};
+use rayon::prelude::*;
+
+/// Algorithm clustering the leaf subclusters into the final clusters
+#[derive(Copy, Clone, Debug)]
+pub enum GlobalClustering {
+    /// KMeans with every subcluster centroid weighted by its number of points
+    KMeans,
+    /// Agglomerative clustering of the subcluster centroids, every subcluster counting as its number of points
+    Agglomerative(Link)
+}
+
+/// Summary (count, linear sum, squared sum) of a set of points, with the node below it if not in a leaf
+struct ClusteringFeature {
+    n: f64,
+    linear_sum: Vec<f64>,
+    squared_sum: f64,
+    child: Option<Box<CFNode>>
+}
+
+struct CFNode {
+    is_leaf: bool,
+    entries: Vec<ClusteringFeature>
+}
+
+impl ClusteringFeature {
+    fn from_point(point: &[f64]) -> Self {
+        ClusteringFeature {
+            n: 1.0,
+            linear_sum: point.to_vec(),
+            squared_sum: point.iter().map(|x| x * x).sum(),
+            child: None
+        }
+    }
+
+    fn from_node(node: CFNode) -> Self {
+        let dimension = node.entries[0].linear_sum.len();
+        let mut feature = ClusteringFeature { n: 0.0, linear_sum: vec![0.0; dimension], squared_sum: 0.0, child: None };
+
+        for entry in node.entries.iter() {
+            feature.add(entry);
+        }
+
+        feature.child = Some(Box::new(node));
+        feature
+    }
+
+    fn add(&mut self, other: &ClusteringFeature) {
+        self.n += other.n;
+        self.squared_sum += other.squared_sum;
+        for (s, x) in self.linear_sum.iter_mut().zip(other.linear_sum.iter()) {
+            *s += x;
+        }
+    }
+
+    fn centroid(&self) -> Vec<f64> {
+        self.linear_sum.iter().map(|s| s / self.n).collect()
+    }
+
+    // Radius of the union with `other`, from the sums alone
+    fn merged_radius(&self, other: &ClusteringFeature) -> f64 {
+        let n = self.n + other.n;
+        let squared_norm = self.linear_sum.iter().zip(other.linear_sum.iter()).map(|(a, b)| {
+            let c = (a + b) / n;
+            c * c
+        }).sum::<f64>();
+
+        ((self.squared_sum + other.squared_sum) / n - squared_norm).max(0.0).sqrt()
+    }
+}
+
+impl CFNode {
+    fn closest(&self, feature: &ClusteringFeature) -> usize {
+        let centroid = feature.centroid();
+
+        (0..self.entries.len()).min_by(|a, b| {
+            SquaredEuclidean::distance(&self.entries[*a].centroid(), &centroid)
+                .partial_cmp(&SquaredEuclidean::distance(&self.entries[*b].centroid(), &centroid))
+                .unwrap_or(Ordering::Equal)
+        }).unwrap()
+    }
+
+    // Inserts the feature below this node and returns whether the node overflowed the branching factor
+    fn insert(&mut self, feature: ClusteringFeature, threshold: f64, branching_factor: usize) -> bool {
+        if self.entries.is_empty() {
+            self.entries.push(feature);
+            return false
+        }
+
+        let closest = self.closest(&feature);
+
+        if self.is_leaf {
+            if self.entries[closest].merged_radius(&feature) <= threshold {
+                self.entries[closest].add(&feature);
+                return false
+            }
+
+            self.entries.push(feature);
+            return self.entries.len() > branching_factor
+        }
+
+        self.entries[closest].add(&feature);
+        let child_overflowed = self.entries[closest].child.as_mut().unwrap().insert(feature, threshold, branching_factor);
+
+        if child_overflowed {
+            let child = *self.entries.swap_remove(closest).child.unwrap();
+            let (first, second) = child.split();
+
+            self.entries.push(ClusteringFeature::from_node(first));
+            self.entries.push(ClusteringFeature::from_node(second));
+        }
+
+        self.entries.len() > branching_factor
+    }
+
+    // Splits the entries around the farthest pair
+    fn split(self) -> (CFNode, CFNode) {
+        let centroids: Vec<Vec<f64>> = self.entries.iter().map(|e| e.centroid()).collect();
+
+        let (seed1, seed2) = (0..centroids.len()).flat_map(|a| ((a + 1)..centroids.len()).map(move |b| (a, b))).max_by(|&(a1, b1), &(a2, b2)| {
+            SquaredEuclidean::distance(&centroids[a1], &centroids[b1])
+                .partial_cmp(&SquaredEuclidean::distance(&centroids[a2], &centroids[b2]))
+                .unwrap_or(Ordering::Equal)
+        }).unwrap();
+
+        let mut first = CFNode { is_leaf: self.is_leaf, entries: vec![] };
+        let mut second = CFNode { is_leaf: self.is_leaf, entries: vec![] };
+
+        for (index_e, entry) in self.entries.into_iter().enumerate() {
+            match SquaredEuclidean::distance(&centroids[index_e], &centroids[seed1]) <= SquaredEuclidean::distance(&centroids[index_e], &centroids[seed2]) {
+                true if index_e != seed2 => first.entries.push(entry),
+                _ => second.entries.push(entry)
+            }
+        }
+
+        (first, second)
+    }
+
+    fn leaves<'a>(&'a self, leaves: &mut Vec<&'a ClusteringFeature>) {
+        for entry in self.entries.iter() {
+            match entry.child {
+                Some(ref child) => child.leaves(leaves),
+                None => leaves.push(entry)
+            }
+        }
+    }
+}
+
+pub struct BIRCH {
+    assignments: Vec<usize>,
+    subcluster_centers: Vec<Point>,
+    subcluster_weights: Vec<f64>,
+    subcluster_labels: Vec<usize>,
+    root: Option<CFNode>,
+    threshold: f64,
+    branching_factor: usize,
+    no_clusters: Option<usize>,
+    global_clustering: GlobalClustering
+}
+
+impl Default for BIRCH {
+    fn default() -> BIRCH {
+        BIRCH {
+            assignments: vec![],
+            subcluster_centers: vec![],
+            subcluster_weights: vec![],
+            subcluster_labels: vec![],
+            root: None,
+            threshold: 0.5,
+            branching_factor: 50,
+            no_clusters: Some(3),
+            global_clustering: GlobalClustering::KMeans
+        }
+    }
+}
+
+impl BIRCH {
+    pub fn new() -> Self {
+        BIRCH::default()
+    }
+
+    /// Builds a new CF-tree over `points` and labels them
+    pub fn run(self, points: &[Point]) -> Self {
+        BIRCH { root: None, .. self }.partial_fit(points)
+    }
+
+    /// Inserts a chunk of points into the CF-tree and reruns the global clustering. The assignments are those of the chunk.
+    pub fn partial_fit(mut self, points: &[Point]) -> Self {
+        let mut root = self.root.take().unwrap_or(CFNode { is_leaf: true, entries: vec![] });
+        let branching_factor = self.branching_factor.max(2);
+
+        for point in points {
+            if root.insert(ClusteringFeature::from_point(point.coordinates()), self.threshold, branching_factor) {
+                let (first, second) = root.split();
+
+                root = CFNode {
+                    is_leaf: false,
+                    entries: vec![ClusteringFeature::from_node(first), ClusteringFeature::from_node(second)]
+                };
+            }
+        }
+
+        let (subcluster_centers, subcluster_weights) = {
+            let mut leaves = vec![];
+            root.leaves(&mut leaves);
+
+            let centers: Vec<Point> = leaves.iter().map(|leaf| Point::new(leaf.centroid())).collect();
+            let weights: Vec<f64> = leaves.iter().map(|leaf| leaf.n).collect();
+            (centers, weights)
+        };
+
+        let subcluster_labels = match self.no_clusters {
+            Some(no_clusters) if no_clusters < subcluster_centers.len() => match self.global_clustering {
+                GlobalClustering::KMeans => KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus)
+                                                         .set_max_iterations(100)
+                                                         .run_weighted(&subcluster_centers, &subcluster_weights, no_clusters)
+                                                         .assignments()
+                                                         .to_vec(),
+                GlobalClustering::Agglomerative(link) => {
+                    let counts: Vec<usize> = subcluster_weights.iter().map(|w| *w as usize).collect();
+                    Agglomerative::new().set_link(link).run_weighted(&subcluster_centers, &counts, no_clusters).assignments().to_vec()
+                }
+            },
+            _ => (0..subcluster_centers.len()).collect()
+        };
+
+        let birch = BIRCH {
+            subcluster_centers,
+            subcluster_weights,
+            subcluster_labels,
+            root: Some(root),
+            .. self
+        };
+
+        BIRCH { assignments: birch.predict(points), .. birch }
+    }
+
+    /// Label of the closest leaf subcluster of every point
+    pub fn predict(&self, points: &[Point]) -> Vec<usize> {
+        points.par_iter().map(|p| {
+            let closest = (0..self.subcluster_centers.len()).min_by(|a, b| {
+                SquaredEuclidean::distance(p.coordinates(), self.subcluster_centers[*a].coordinates())
+                    .partial_cmp(&SquaredEuclidean::distance(p.coordinates(), self.subcluster_centers[*b].coordinates()))
+                    .unwrap_or(Ordering::Equal)
+            }).unwrap();
+
+            self.subcluster_labels[closest]
+        }).collect()
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Centroids of the leaf subclusters
+    pub fn subcluster_centers(&self) -> &[Point] { &self.subcluster_centers }
+
+    /// Number of points summarised by every leaf subcluster
+    pub fn subcluster_weights(&self) -> &[f64] { &self.subcluster_weights }
+
+    /// Global cluster of every leaf subcluster
+    pub fn subcluster_labels(&self) -> &[usize] { &self.subcluster_labels }
+
+    /// Maximum radius of a leaf subcluster absorbing a new point
+    pub fn set_threshold(self, threshold: f64) -> Self {
+        BIRCH { threshold, .. self }
+    }
+
+    /// Maximum number of entries in a node, at least 2 so that a split leaves an entry on each side
+    pub fn set_branching_factor(self, branching_factor: usize) -> Self {
+        BIRCH { branching_factor, .. self }
+    }
+
+    /// Number of global clusters, or None to keep every leaf subcluster as a cluster
+    pub fn set_no_clusters(self, no_clusters: Option<usize>) -> Self {
+        BIRCH { no_clusters, .. self }
+    }
+
+    pub fn set_global_clustering(self, global_clustering: GlobalClustering) -> Self {
+        BIRCH { global_clustering, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 300, 0.3, 0).data().clone()
+    }
+
+    fn separates_blobs(assignments: &[usize]) -> bool {
+        (0..3).all(|blob| assignments[blob * 300..(blob + 1) * 300].iter().all(|a| *a == assignments[blob * 300])) &&
+            assignments[0] != assignments[300] && assignments[0] != assignments[600] && assignments[300] != assignments[600]
+    }
+
+    #[test]
+    fn can_run_birch() {
+        let points = blobs();
+
+        let output = BIRCH::new().set_threshold(0.3).set_branching_factor(10).run(&points);
+        assert!(separates_blobs(output.assignments()));
+        assert_eq!(points.len() as f64, output.subcluster_weights().iter().sum::<f64>());
+
+        for link in [Link::Single, Link::Average, Link::Ward].iter() {
+            let output = BIRCH::new().set_threshold(0.3).set_global_clustering(GlobalClustering::Agglomerative(*link)).run(&points);
+            assert!(separates_blobs(output.assignments()));
+        }
+    }
+
+    #[test]
+    fn can_run_with_small_branching_factor() {
+        let points = blobs();
+
+        for branching_factor in [0, 1, 2].iter() {
+            let output = BIRCH::new().set_threshold(0.3).set_branching_factor(*branching_factor).run(&points);
+            assert_eq!(points.len() as f64, output.subcluster_weights().iter().sum::<f64>());
+        }
+    }
+
+    #[test]
+    fn can_partial_fit_chunks() {
+        let mut points = blobs();
+        rand::thread_rng().shuffle(&mut points);
+
+        let output = points.chunks(100).fold(BIRCH::new().set_threshold(0.3), |birch, chunk| birch.partial_fit(chunk));
+        assert_eq!(points.len() as f64, output.subcluster_weights().iter().sum::<f64>());
+
+        let assignments = output.predict(&blobs());
+        assert!(separates_blobs(&assignments));
+    }
+}
@@ -0,0 +1,274 @@
+// https://www.cs.umn.edu/sites/cs.umn.edu/files/tech_reports/00-034.pdf
+
+use std::cmp::Ordering;
+use std::f64;
+use point::Point;
+use clustering::bisecting_kmeans::BisectingStrategy::*;
+use clustering::kmeans::{KMeans, KMeansInitialization};
+use clustering::agglomerative::Merge;
+use clustering::dendrogram::Dendrogram;
+use statistics::distance::{Distance, SquaredEuclidean};
+use statistics::statistics::Statistics;
+
+#[derive(Copy, Clone, Debug)]
+pub enum BisectingStrategy {
+    /// Split the cluster with the largest sum of squared errors
+    LargestSSE,
+    /// Split the cluster with the most points
+    LargestCluster
+}
+
+/// A cluster of the split tree, the root being the whole data set
+#[derive(Clone, Debug)]
+pub struct SplitNode {
+    pub centroid: Point,
+    pub points: Vec<usize>,
+    pub sse: f64,
+    /// Indices of the two halves in `tree()`, if the cluster was split
+    pub children: Option<(usize, usize)>,
+    /// Cluster of the flat clustering, if the node is a leaf
+    pub label: Option<usize>
+}
+
+pub struct BisectingKMeans {
+    assignments: Vec<usize>,
+    centroids: Vec<Point>,
+    tree: Vec<SplitNode>,
+    strategy: BisectingStrategy,
+    no_trials: usize,
+    max_iterations: usize
+}
+
+impl Default for BisectingKMeans {
+    fn default() -> BisectingKMeans {
+        BisectingKMeans {
+            assignments: vec![],
+            centroids: vec![],
+            tree: vec![],
+            strategy: LargestSSE,
+            no_trials: 5,
+            max_iterations: 100
+        }
+    }
+}
+
+impl BisectingKMeans {
+    pub fn new() -> Self {
+        BisectingKMeans::default()
+    }
+
+    pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
+        if points.is_empty() || no_clusters == 0 {
+            return BisectingKMeans { assignments: vec![], centroids: vec![], tree: vec![], .. self }
+        }
+
+        let mut tree = vec![Self::node(points, (0..points.len()).collect())];
+        let mut leaves = vec![0];
+        let mut unsplittable: Vec<usize> = vec![];
+
+        while leaves.len() < no_clusters {
+            let candidate = leaves.iter().cloned().filter(|index_n| !unsplittable.contains(index_n) && tree[*index_n].points.len() > 1).max_by(|a, b| {
+                match self.strategy {
+                    LargestSSE => tree[*a].sse.partial_cmp(&tree[*b].sse).unwrap_or(Ordering::Equal),
+                    LargestCluster => tree[*a].points.len().cmp(&tree[*b].points.len())
+                }
+            });
+
+            let index_n = match candidate {
+                Some(index_n) => index_n,
+                None => break
+            };
+
+            match self.bisect(points, &tree[index_n].points) {
+                Some((first, second)) => {
+                    let first_index = tree.len();
+                    tree.push(Self::node(points, first));
+                    tree.push(Self::node(points, second));
+                    tree[index_n].children = Some((first_index, first_index + 1));
+
+                    let position = leaves.iter().position(|l| *l == index_n).unwrap();
+                    leaves.remove(position);
+                    leaves.push(first_index);
+                    leaves.push(first_index + 1);
+                },
+                None => unsplittable.push(index_n)
+            }
+        }
+
+        let mut assignments = vec![0; points.len()];
+        for (label, index_n) in leaves.iter().enumerate() {
+            tree[*index_n].label = Some(label);
+            for index_p in tree[*index_n].points.iter() {
+                assignments[*index_p] = label;
+            }
+        }
+
+        BisectingKMeans {
+            assignments,
+            centroids: leaves.iter().map(|index_n| tree[*index_n].centroid.clone()).collect(),
+            tree,
+            .. self
+        }
+    }
+
+    // Best of several 2-means runs on the given points, by total SSE
+    fn bisect(&self, points: &[Point], members: &[usize]) -> Option<(Vec<usize>, Vec<usize>)> {
+        let cluster: Vec<Point> = members.iter().map(|index_p| points[*index_p].clone()).collect();
+
+        (0..self.no_trials.max(1)).filter_map(|_| {
+            let kmeans = KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus)
+                                      .set_max_iterations(self.max_iterations)
+                                      .run(&cluster, 2);
+
+            let first: Vec<usize> = members.iter().zip(kmeans.assignments().iter()).filter(|&(_, a)| *a == 0).map(|(index_p, _)| *index_p).collect();
+            let second: Vec<usize> = members.iter().zip(kmeans.assignments().iter()).filter(|&(_, a)| *a != 0).map(|(index_p, _)| *index_p).collect();
+            if first.is_empty() || second.is_empty() {
+                return None
+            }
+
+            let sse = Self::node(points, first.clone()).sse + Self::node(points, second.clone()).sse;
+
+            Some((sse, first, second))
+        }).min_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal)).map(|(_, first, second)| (first, second))
+    }
+
+    fn node(points: &[Point], members: Vec<usize>) -> SplitNode {
+        let coordinates: Vec<&[f64]> = members.iter().map(|index_p| points[*index_p].coordinates()).collect();
+        let centroid = Statistics::mean(&coordinates);
+        let sse = coordinates.iter().map(|c| SquaredEuclidean::distance(c, &centroid)).sum();
+
+        SplitNode {
+            centroid: Point::new(centroid),
+            points: members,
+            sse,
+            children: None,
+            label: None
+        }
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// Every cluster ever formed, the root first and the two halves of a split added together
+    pub fn tree(&self) -> &[SplitNode] { &self.tree }
+
+    /// The split tree as a linkage over the clusters of the flat clustering, every split becoming a merge at the SSE of the parent.
+    /// Sizes count the points rather than the leaves.
+    pub fn linkage(&self) -> Vec<Merge> {
+        let no_leaves = self.centroids.len();
+
+        // A parent has a larger SSE than either half, so in order of SSE the halves always come first
+        let mut splits: Vec<usize> = (0..self.tree.len()).filter(|index_n| self.tree[*index_n].children.is_some()).collect();
+        splits.sort_by(|a, b| self.tree[*a].sse.partial_cmp(&self.tree[*b].sse).unwrap_or(Ordering::Equal));
+
+        let mut ids = vec![0; self.tree.len()];
+        for (index_n, node) in self.tree.iter().enumerate() {
+            if let Some(label) = node.label {
+                ids[index_n] = label;
+            }
+        }
+        for (index_s, index_n) in splits.iter().enumerate() {
+            ids[*index_n] = no_leaves + index_s;
+        }
+
+        splits.iter().map(|index_n| {
+            let (first, second) = self.tree[*index_n].children.unwrap();
+
+            Merge {
+                first: ids[first].min(ids[second]),
+                second: ids[first].max(ids[second]),
+                distance: self.tree[*index_n].sse,
+                size: self.tree[*index_n].points.len()
+            }
+        }).collect()
+    }
+
+    pub fn dendrogram(&self) -> Dendrogram {
+        Dendrogram::new(&self.linkage(), self.centroids.len())
+    }
+
+    /// Total sum of squared errors of the flat clustering
+    pub fn sse(&self) -> f64 {
+        self.tree.iter().filter(|node| node.label.is_some()).map(|node| node.sse).sum()
+    }
+
+    pub fn set_strategy(self, strategy: BisectingStrategy) -> Self {
+        BisectingKMeans { strategy, .. self }
+    }
+
+    /// Number of 2-means runs per split, the one with the lowest SSE being kept
+    pub fn set_no_trials(self, no_trials: usize) -> Self {
+        BisectingKMeans { no_trials, .. self }
+    }
+
+    /// Maximum number of iterations of every inner KMeans run
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        BisectingKMeans { max_iterations, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0], &[20.0, 0.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_bisecting_kmeans() {
+        let points = blobs();
+
+        for strategy in [LargestSSE, LargestCluster].iter() {
+            let output = BisectingKMeans::new().set_strategy(*strategy).run(&points, 4);
+
+            assert_eq!(4, output.centroids().len());
+            for blob in 0..4 {
+                assert!(output.assignments()[blob * 100..(blob + 1) * 100].iter().all(|a| *a == output.assignments()[blob * 100]));
+            }
+        }
+    }
+
+    #[test]
+    fn can_run_without_points_or_clusters() {
+        let output = BisectingKMeans::new().run(&[], 3);
+        assert!(output.centroids().is_empty());
+        assert!(output.tree().is_empty());
+
+        let output = BisectingKMeans::new().run(&blobs(), 0);
+        assert!(output.centroids().is_empty());
+        assert!(output.assignments().is_empty());
+        assert!(output.linkage().is_empty());
+        assert_eq!(0.0, output.sse());
+    }
+
+    #[test]
+    fn split_tree_is_consistent() {
+        let points = blobs();
+        let output = BisectingKMeans::new().run(&points, 4);
+        let tree = output.tree();
+
+        assert_eq!(7, tree.len());
+        assert_eq!(points.len(), tree[0].points.len());
+        for node in tree {
+            match node.children {
+                Some((first, second)) => {
+                    assert!(node.label.is_none());
+                    assert_eq!(node.points.len(), tree[first].points.len() + tree[second].points.len());
+                    assert!(tree[first].sse + tree[second].sse <= node.sse);
+                },
+                None => assert!(node.label.is_some())
+            }
+        }
+
+        let linkage = output.linkage();
+        assert_eq!(3, linkage.len());
+        assert_eq!(points.len(), linkage[2].size);
+        assert!(linkage.windows(2).all(|w| w[0].distance <= w[1].distance));
+        assert_eq!(3, output.dendrogram().to_newick().matches('(').count());
+    }
+}
@@ -0,0 +1,159 @@
+// Kaufman, L. and Rousseeuw, P.J. (1990), Finding Groups in Data, chapter 3
+
+use rand;
+use rand::Rng;
+use std::cmp::Ordering;
+use std::f64;
+use point::Point;
+use statistics::distance::Distance;
+use clustering::kmedoids::{KMedoids, KMedoidsAlgorithm, KMedoidsInitialization};
+use rayon::prelude::*;
+
+pub struct CLARA {
+    assignments: Vec<usize>,
+    medoids: Vec<usize>,
+    centroids: Vec<Point>,
+    deviation: f64,
+    algorithm: KMedoidsAlgorithm,
+    no_samples: usize,
+    sample_size: Option<usize>
+}
+
+impl Default for CLARA {
+    fn default() -> CLARA {
+        CLARA {
+            assignments: vec![],
+            medoids: vec![],
+            centroids: vec![],
+            deviation: 0.0,
+            algorithm: KMedoidsAlgorithm::FastPAM1,
+            no_samples: 5,
+            sample_size: None
+        }
+    }
+}
+
+impl CLARA {
+    pub fn new() -> Self {
+        CLARA::default()
+    }
+
+    pub fn run<D: Distance>(self, points: &[Point], no_clusters: usize) -> Self {
+        let no_clusters = no_clusters.min(points.len());
+        if no_clusters == 0 {
+            return CLARA { assignments: vec![], medoids: vec![], centroids: vec![], deviation: 0.0, .. self }
+        }
+
+        let sample_size = self.sample_size.unwrap_or(40 + 2 * no_clusters).min(points.len());
+
+        // Every sample is clustered on its own and its medoids judged on the whole data set
+        let best = (0..self.no_samples.max(1)).into_par_iter().map(|_| {
+            let mut sample: Vec<usize> = (0..points.len()).collect();
+            rand::thread_rng().shuffle(&mut sample);
+            sample.truncate(sample_size);
+
+            let distances: Vec<Vec<f64>> = sample.iter().map(|index_p| {
+                sample.iter().map(|index_q| D::distance(points[*index_p].coordinates(), points[*index_q].coordinates())).collect()
+            }).collect();
+
+            let medoids: Vec<usize> = KMedoids::new().set_algorithm(self.algorithm)
+                                                     .set_init_method(KMedoidsInitialization::Build)
+                                                     .run_precomputed(&distances, no_clusters)
+                                                     .medoids()
+                                                     .iter()
+                                                     .map(|index_m| sample[*index_m])
+                                                     .collect();
+
+            let deviation = points.iter().map(|p| Self::closest_medoid::<D>(p, points, &medoids).1).sum::<f64>();
+            (medoids, deviation)
+        }).min_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+
+        let (medoids, deviation) = best.unwrap_or((vec![], 0.0));
+
+        CLARA {
+            assignments: points.par_iter().map(|p| Self::closest_medoid::<D>(p, points, &medoids).0).collect(),
+            centroids: medoids.iter().map(|index_m| points[*index_m].clone()).collect(),
+            medoids,
+            deviation,
+            .. self
+        }
+    }
+
+    #[inline]
+    fn closest_medoid<D: Distance>(point: &Point, points: &[Point], medoids: &[usize]) -> (usize, f64) {
+        medoids.iter().enumerate().map(|(index_m, m)| {
+            (index_m, D::distance(point.coordinates(), points[*m].coordinates()))
+        }).min_by(|&(_, a), &(_, b)| {
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }).unwrap_or((0, f64::INFINITY))
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Indices of the medoids among the clustered points
+    pub fn medoids(&self) -> &[usize] { &self.medoids }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// Sum of the distances of every point, sampled or not, to its medoid
+    pub fn deviation(&self) -> f64 { self.deviation }
+
+    /// Algorithm clustering every sample, started from the BUILD initialization
+    pub fn set_algorithm(self, algorithm: KMedoidsAlgorithm) -> Self {
+        CLARA { algorithm, .. self }
+    }
+
+    pub fn set_no_samples(self, no_samples: usize) -> Self {
+        CLARA { no_samples, .. self }
+    }
+
+    /// Number of points per sample, 40 + 2k if not set
+    pub fn set_sample_size(self, sample_size: Option<usize>) -> Self {
+        CLARA { sample_size, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::blobs::make_blobs;
+    use statistics::distance::Euclidean;
+
+    fn blobs(size: usize) -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], size, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_clara() {
+        let points = blobs(2000);
+        let output = CLARA::new().run::<Euclidean>(&points, 3);
+
+        assert_eq!(3, output.centroids().len());
+        for blob in 0..3 {
+            assert!(output.assignments()[blob * 2000..(blob + 1) * 2000].iter().all(|a| *a == output.assignments()[blob * 2000]));
+        }
+    }
+
+    #[test]
+    fn whole_sample_matches_pam() {
+        let points = blobs(50);
+        let clara = CLARA::new().set_no_samples(2).set_sample_size(Some(points.len())).run::<Euclidean>(&points, 3);
+        let pam = KMedoids::new().set_algorithm(KMedoidsAlgorithm::PAM).run::<Euclidean>(&points, 3);
+
+        assert!((clara.deviation() - pam.deviation()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn can_run_without_clusters() {
+        let output = CLARA::new().run::<Euclidean>(&blobs(50), 0);
+        assert!(output.medoids().is_empty());
+        assert!(output.assignments().is_empty());
+        assert_eq!(0.0, output.deviation());
+
+        let output = CLARA::new().run::<Euclidean>(&[], 3);
+        assert!(output.medoids().is_empty());
+        assert!(output.centroids().is_empty());
+    }
+}
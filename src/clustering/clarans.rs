@@ -0,0 +1,205 @@
+// http://www.cs.ecu.edu/dingq/CSCI6905/readings/CLARANS.pdf
+
+use rand;
+use rand::{Rng, SeedableRng, StdRng};
+use rand::distributions::{IndependentSample, Range};
+
+use std::cmp::Ordering;
+use std::f64;
+use point::Point;
+use statistics::distance::Distance;
+use rayon::prelude::*;
+
+pub struct Clarans {
+    assignments: Vec<usize>,
+    medoids: Vec<usize>,
+    centroids: Vec<Point>,
+    cost: f64,
+    seed: Option<usize>,
+    num_local: usize,
+    max_neighbor: Option<usize>
+}
+
+impl Default for Clarans {
+    fn default() -> Clarans {
+        Clarans {
+            assignments: vec![],
+            medoids: vec![],
+            centroids: vec![],
+            cost: 0.0,
+            seed: None,
+            num_local: 2,
+            max_neighbor: None
+        }
+    }
+}
+
+impl Clarans {
+    pub fn new() -> Self {
+        Clarans::default()
+    }
+
+    /// Randomized search over the graph of medoid sets, neighbors differing by a single medoid
+    pub fn run<D: Distance>(self, points: &[Point], no_clusters: usize) -> Self {
+        let no_clusters = no_clusters.min(points.len());
+        if no_clusters == 0 {
+            return Clarans { assignments: vec![], medoids: vec![], centroids: vec![], cost: 0.0, .. self }
+        }
+
+        let seed = self.seed.unwrap_or_else(|| rand::thread_rng().gen());
+        let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+
+        // Ng and Han suggest 1.25% of the k(n - k) neighbors, but at least 250
+        let max_neighbor = self.max_neighbor.unwrap_or_else(|| {
+            250.max(no_clusters * (points.len() - no_clusters) / 80)
+        });
+
+        let mut optimal_medoids = vec![];
+        let mut optimal_cost = f64::INFINITY;
+
+        for _ in 0..self.num_local.max(1) {
+            let mut medoids: Vec<usize> = (0..points.len()).collect();
+            rng.shuffle(&mut medoids);
+            medoids.truncate(no_clusters);
+
+            let mut nearest = Self::nearest::<D>(points, &medoids);
+
+            let mut index_neighbor = 0;
+            while index_neighbor < max_neighbor && no_clusters < points.len() {
+                let index_m = Range::new(0, no_clusters).ind_sample(&mut rng);
+                let mut candidate = Range::new(0, points.len()).ind_sample(&mut rng);
+                while medoids.contains(&candidate) {
+                    candidate = Range::new(0, points.len()).ind_sample(&mut rng);
+                }
+
+                // Every point either stays, moves to the candidate or falls back to its second nearest medoid.
+                // The changes are summed in order, so that a seed always leads to the same swaps.
+                let changes: Vec<f64> = points.par_iter().zip(nearest.par_iter()).map(|(p, &(index_c, first, second))| {
+                    let distance = D::distance(p.coordinates(), points[candidate].coordinates());
+
+                    match index_c == index_m {
+                        true => distance.min(second) - first,
+                        false => (distance - first).min(0.0)
+                    }
+                }).collect();
+                let delta: f64 = changes.iter().sum();
+
+                let cost = nearest.iter().map(|&(_, first, _)| first).sum::<f64>();
+
+                // Relative to the cost, so that rounding errors cannot make swaps go around in circles
+                if delta < -f64::EPSILON * cost {
+                    medoids[index_m] = candidate;
+                    nearest = Self::nearest::<D>(points, &medoids);
+                    index_neighbor = 0;
+                } else {
+                    index_neighbor += 1;
+                }
+            }
+
+            let cost = nearest.iter().map(|&(_, first, _)| first).sum();
+            if cost < optimal_cost {
+                optimal_medoids = medoids;
+                optimal_cost = cost;
+            }
+        }
+
+        Clarans {
+            assignments: Self::nearest::<D>(points, &optimal_medoids).into_iter().map(|(index_c, _, _)| index_c).collect(),
+            centroids: optimal_medoids.iter().map(|index_m| points[*index_m].clone()).collect(),
+            medoids: optimal_medoids,
+            cost: if optimal_cost.is_finite() { optimal_cost } else { 0.0 },
+            .. self
+        }
+    }
+
+    // Position of the nearest medoid, with the distances to the nearest and second nearest medoids
+    fn nearest<D: Distance>(points: &[Point], medoids: &[usize]) -> Vec<(usize, f64, f64)> {
+        points.par_iter().map(|p| {
+            medoids.iter().enumerate().fold((0, f64::INFINITY, f64::INFINITY), |(nearest, first, second), (index_m, m)| {
+                let distance = D::distance(p.coordinates(), points[*m].coordinates());
+
+                if distance < first {
+                    (index_m, distance, first)
+                } else if distance < second {
+                    (nearest, first, distance)
+                } else {
+                    (nearest, first, second)
+                }
+            })
+        }).collect()
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Indices of the medoids among the clustered points
+    pub fn medoids(&self) -> &[usize] { &self.medoids }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// Sum of the distances of every point to its medoid
+    pub fn cost(&self) -> f64 { self.cost }
+
+    /// Seed of the random search, making runs reproducible
+    pub fn set_seed(self, seed: Option<usize>) -> Self {
+        Clarans { seed, .. self }
+    }
+
+    /// Number of local minima searched, the best one being kept. At least one is always searched
+    pub fn set_num_local(self, num_local: usize) -> Self {
+        Clarans { num_local, .. self }
+    }
+
+    /// Number of random neighbors tried without improvement before a medoid set counts as a local minimum
+    pub fn set_max_neighbor(self, max_neighbor: Option<usize>) -> Self {
+        Clarans { max_neighbor, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::*;
+    use clustering::kmedoids::KMedoids;
+    use statistics::distance::{Euclidean, Manhattan};
+
+    #[test]
+    fn can_run_clarans_iris() {
+        let output = Clarans::new().run::<Euclidean>(iris::load().data(), 3);
+        let pam = KMedoids::new().run::<Euclidean>(iris::load().data(), 3);
+
+        assert_eq!(3, output.medoids().len());
+        assert_eq!(150, output.assignments().len());
+        assert!(output.cost() <= pam.deviation() * 1.05);
+        assert!(output.assignments()[..50].iter().all(|a| *a == output.assignments()[0]));
+        assert!(output.assignments()[50..].iter().all(|a| *a != output.assignments()[0]));
+    }
+
+    #[test]
+    fn can_run_without_clusters() {
+        let output = Clarans::new().run::<Euclidean>(iris::load().data(), 0);
+        assert!(output.medoids().is_empty());
+
+        let output = Clarans::new().run::<Euclidean>(&[], 3);
+        assert!(output.medoids().is_empty());
+    }
+
+    #[test]
+    fn searches_at_least_one_local_minimum() {
+        let output = Clarans::new().set_num_local(0).set_max_neighbor(Some(50)).run::<Euclidean>(iris::load().data(), 3);
+
+        assert_eq!(3, output.medoids().len());
+        assert!(output.assignments().iter().all(|a| *a < 3));
+        assert!(output.cost() > 0.0);
+    }
+
+    #[test]
+    fn clarans_is_reproducible_with_seed() {
+        let first = Clarans::new().set_seed(Some(42)).set_max_neighbor(Some(50)).run::<Manhattan>(iris::load().data(), 3);
+        let second = Clarans::new().set_seed(Some(42)).set_max_neighbor(Some(50)).run::<Manhattan>(iris::load().data(), 3);
+
+        assert_eq!(first.medoids(), second.medoids());
+        assert_eq!(first.assignments(), second.assignments());
+    }
+}
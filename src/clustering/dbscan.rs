@@ -0,0 +1,196 @@
+// https://www.aaai.org/Papers/KDD/1996/KDD96-037.pdf
+
+use std::usize;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::NeighborSearch;
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PointType {
+    /// Has at least `min_points` neighbours within `eps`, itself included
+    Core,
+    /// Within `eps` of a core point without being one
+    Border,
+    Noise
+}
+
+pub struct DBSCAN {
+    assignments: Vec<Option<usize>>,
+    point_types: Vec<PointType>,
+    no_clusters: usize,
+    eps: f64,
+    min_points: usize,
+    neighbor_search: NeighborSearch
+}
+
+impl Default for DBSCAN {
+    fn default() -> DBSCAN {
+        DBSCAN {
+            assignments: vec![],
+            point_types: vec![],
+            no_clusters: 0,
+            eps: 0.5,
+            min_points: 5,
+            neighbor_search: NeighborSearch::BruteForce
+        }
+    }
+}
+
+impl DBSCAN {
+    pub fn new() -> Self {
+        DBSCAN::default()
+    }
+
+    pub fn run<D: Distance>(self, points: &[Point]) -> Self {
+        let index = self.neighbor_search.build::<D>(points, self.eps);
+
+        let neighborhoods: Vec<Vec<usize>> = points.par_iter().map(|p| {
+            index.within(p.coordinates(), self.eps).into_iter().map(|(index_p, _)| index_p).collect()
+        }).collect();
+
+        let is_core: Vec<bool> = neighborhoods.iter().map(|neighbors| neighbors.len() >= self.min_points).collect();
+
+        let mut assignments: Vec<Option<usize>> = vec![None; points.len()];
+        let mut no_clusters = 0;
+
+        for index_p in 0..points.len() {
+            if !is_core[index_p] || assignments[index_p].is_some() {
+                continue;
+            }
+
+            assignments[index_p] = Some(no_clusters);
+            let mut frontier = vec![index_p];
+
+            // Only core points extend the cluster, border points are claimed by the first cluster reaching them
+            while let Some(current) = frontier.pop() {
+                for &neighbor in neighborhoods[current].iter() {
+                    if assignments[neighbor].is_none() {
+                        assignments[neighbor] = Some(no_clusters);
+
+                        if is_core[neighbor] {
+                            frontier.push(neighbor);
+                        }
+                    }
+                }
+            }
+
+            no_clusters += 1;
+        }
+
+        let point_types = is_core.iter().zip(assignments.iter()).map(|(core, assignment)| {
+            match (*core, *assignment) {
+                (true, _) => PointType::Core,
+                (false, Some(_)) => PointType::Border,
+                (false, None) => PointType::Noise
+            }
+        }).collect();
+
+        DBSCAN {
+            assignments,
+            point_types,
+            no_clusters,
+            .. self
+        }
+    }
+
+    /// Cluster of every point, `None` for noise
+    pub fn assignments(&self) -> &[Option<usize>] { &self.assignments }
+
+    pub fn point_types(&self) -> &[PointType] { &self.point_types }
+
+    pub fn core_points(&self) -> Vec<usize> {
+        self.point_types.iter().enumerate().filter(|&(_, t)| *t == PointType::Core).map(|(index_p, _)| index_p).collect()
+    }
+
+    pub fn noise(&self) -> Vec<usize> {
+        self.assignments.iter().enumerate().filter(|&(_, a)| a.is_none()).map(|(index_p, _)| index_p).collect()
+    }
+
+    pub fn no_clusters(&self) -> usize { self.no_clusters }
+
+    pub fn set_eps(self, eps: f64) -> Self {
+        DBSCAN { eps, .. self }
+    }
+
+    pub fn set_min_points(self, min_points: usize) -> Self {
+        DBSCAN { min_points, .. self }
+    }
+
+    pub fn set_neighbor_search(self, neighbor_search: NeighborSearch) -> Self {
+        DBSCAN { neighbor_search, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use statistics::distance::{Euclidean, SquaredEuclidean, CosineDistance};
+    use datasets::blobs::make_blobs;
+
+    fn blobs_with_outlier() -> Vec<Point> {
+        let mut points = make_blobs(&[&[0.0, 0.0], &[5.0, 5.0]], 200, 0.15, 0).data().clone();
+
+        points.push(Point::new(vec![20.0, -20.0]));
+        points
+    }
+
+    #[test]
+    fn can_run_dbscan() {
+        let points = blobs_with_outlier();
+        let output = DBSCAN::new().set_eps(0.3).set_min_points(4).run::<Euclidean>(&points);
+
+        assert_eq!(2, output.no_clusters());
+        assert_eq!(vec![400], output.noise());
+        assert_eq!(PointType::Noise, output.point_types()[400]);
+        assert!(output.assignments()[..200].iter().all(|a| *a == output.assignments()[0]));
+        assert!(output.assignments()[200..400].iter().all(|a| *a == output.assignments()[200]));
+        assert!(output.core_points().len() > 0);
+    }
+
+    #[test]
+    fn neighbor_search_backends_agree() {
+        let points = blobs_with_outlier();
+        let expected = DBSCAN::new().set_eps(0.3).set_neighbor_search(NeighborSearch::BruteForce).run::<Euclidean>(&points);
+
+        for search in [NeighborSearch::KDTree, NeighborSearch::Grid].iter() {
+            let output = DBSCAN::new().set_eps(0.3).set_neighbor_search(*search).run::<Euclidean>(&points);
+
+            assert_eq!(expected.point_types(), output.point_types());
+            assert_eq!(expected.no_clusters(), output.no_clusters());
+        }
+    }
+
+    #[test]
+    fn squared_euclidean_matches_euclidean() {
+        let points = blobs_with_outlier();
+        let expected = DBSCAN::new().set_eps(0.3).set_min_points(4).run::<Euclidean>(&points);
+        let output = DBSCAN::new().set_eps(0.09).set_min_points(4).run::<SquaredEuclidean>(&points);
+
+        assert_eq!(expected.point_types(), output.point_types());
+        assert_eq!(2, output.no_clusters());
+    }
+
+    #[test]
+    fn cosine_distance_matches_euclidean_on_directions() {
+        let mut rng = rand::thread_rng();
+        let angles: Vec<f64> = [0.0, 1.5].iter().flat_map(|&angle| {
+            (0..200).map(|_| angle + 0.2 * rng.next_f64()).collect::<Vec<f64>>()
+        }).collect();
+
+        let points: Vec<Point> = angles.iter().map(|a| {
+            let radius = 1.0 + 9.0 * rng.next_f64();
+            Point::new(vec![radius * a.cos(), radius * a.sin()])
+        }).collect();
+        let directions: Vec<Point> = angles.iter().map(|a| Point::new(vec![a.cos(), a.sin()])).collect();
+
+        // Between unit vectors, the cosine distance is half the squared euclidean distance
+        let expected = DBSCAN::new().set_eps((2.0 * 0.001f64).sqrt()).set_min_points(4).run::<Euclidean>(&directions);
+        let output = DBSCAN::new().set_eps(0.001).set_min_points(4).run::<CosineDistance>(&points);
+
+        assert_eq!(expected.point_types(), output.point_types());
+        assert_eq!(2, output.no_clusters());
+    }
+}
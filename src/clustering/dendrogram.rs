@@ -0,0 +1,323 @@
+use std::f64;
+use std::fmt::Write;
+use clustering::agglomerative::Merge;
+
+/// Exports a linkage as Newick, nested JSON or a standalone SVG drawing
+pub struct Dendrogram {
+    linkage: Vec<Merge>,
+    no_leaves: usize,
+    labels: Option<Vec<String>>,
+    cut: Option<f64>,
+    width: f64,
+    height: f64
+}
+
+// Iterative traversal steps, so that deep trees such as single linkage chains cannot overflow the stack
+enum Step {
+    Visit(usize, Option<f64>),
+    Close(usize, Option<f64>),
+    Write(&'static str)
+}
+
+impl Dendrogram {
+    /// Tree of the `no_leaves - 1` merges of a linkage over `no_leaves` leaves, possibly none
+    pub fn new(linkage: &[Merge], no_leaves: usize) -> Self {
+        if linkage.len() + 1 != no_leaves.max(1) {
+            panic!("Expected {} merges for {} leaves, found {}", no_leaves.saturating_sub(1), no_leaves, linkage.len())
+        }
+
+        Dendrogram {
+            linkage: linkage.to_vec(),
+            no_leaves,
+            labels: None,
+            cut: None,
+            width: 800.0,
+            height: 400.0
+        }
+    }
+
+    fn no_leaves(&self) -> usize {
+        self.no_leaves
+    }
+
+    fn root(&self) -> usize {
+        2 * self.linkage.len()
+    }
+
+    fn node_height(&self, node: usize) -> f64 {
+        match node < self.no_leaves() {
+            true => 0.0,
+            false => self.linkage[node - self.no_leaves()].distance
+        }
+    }
+
+    fn label(&self, leaf: usize) -> String {
+        match self.labels {
+            Some(ref labels) if leaf < labels.len() => labels[leaf].clone(),
+            _ => leaf.to_string()
+        }
+    }
+
+    /// Newick tree, branch lengths being the differences of merge heights. An empty tree is just ";"
+    pub fn to_newick(&self) -> String {
+        let mut newick = String::new();
+        let mut stack = if self.no_leaves() > 0 { vec![Step::Visit(self.root(), None)] } else { vec![] };
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Visit(node, parent_height) if node < self.no_leaves() => {
+                    newick.push_str(&Self::newick_label(&self.label(node)));
+                    Self::branch_length(&mut newick, parent_height, 0.0);
+                },
+                Step::Visit(node, parent_height) => {
+                    let merge = &self.linkage[node - self.no_leaves()];
+                    newick.push('(');
+                    stack.push(Step::Close(node, parent_height));
+                    stack.push(Step::Visit(merge.second, Some(merge.distance)));
+                    stack.push(Step::Write(","));
+                    stack.push(Step::Visit(merge.first, Some(merge.distance)));
+                },
+                Step::Close(node, parent_height) => {
+                    newick.push(')');
+                    Self::branch_length(&mut newick, parent_height, self.node_height(node));
+                },
+                Step::Write(text) => newick.push_str(text)
+            }
+        }
+
+        newick.push(';');
+        newick
+    }
+
+    fn branch_length(newick: &mut String, parent_height: Option<f64>, height: f64) {
+        if let Some(parent_height) = parent_height {
+            write!(newick, ":{}", parent_height - height).unwrap();
+        }
+    }
+
+    // Labels with spaces or Newick punctuation are quoted, quotes being doubled
+    fn newick_label(label: &str) -> String {
+        match label.chars().any(|c| c.is_whitespace() || "()[]':;,".contains(c)) {
+            true => format!("'{}'", label.replace('\'', "''")),
+            false => label.to_string()
+        }
+    }
+
+    /// Nested JSON objects with the id, height and size of every node, leaves having a label and inner nodes their two children.
+    /// An empty tree is `null`, and so are infinite or NaN heights
+    pub fn to_json(&self) -> String {
+        if self.no_leaves() == 0 {
+            return "null".to_string()
+        }
+
+        let mut json = String::new();
+        let mut stack = vec![Step::Visit(self.root(), None)];
+
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Visit(node, _) if node < self.no_leaves() => {
+                    write!(json, "{{\"id\":{},\"label\":{},\"height\":0,\"size\":1}}", node, Self::json_string(&self.label(node))).unwrap();
+                },
+                Step::Visit(node, _) => {
+                    let merge = &self.linkage[node - self.no_leaves()];
+                    write!(json, "{{\"id\":{},\"height\":{},\"size\":{},\"children\":[", node, Self::json_number(merge.distance), merge.size).unwrap();
+                    stack.push(Step::Write("]}"));
+                    stack.push(Step::Visit(merge.second, None));
+                    stack.push(Step::Write(","));
+                    stack.push(Step::Visit(merge.first, None));
+                },
+                Step::Close(_, _) => (),
+                Step::Write(text) => json.push_str(text)
+            }
+        }
+
+        json
+    }
+
+    fn json_number(x: f64) -> String {
+        match x.is_finite() {
+            true => x.to_string(),
+            false => "null".to_string()
+        }
+    }
+
+    fn json_string(text: &str) -> String {
+        let mut escaped = String::with_capacity(text.len() + 2);
+        escaped.push('"');
+
+        for c in text.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                '\r' => escaped.push_str("\\r"),
+                '\t' => escaped.push_str("\\t"),
+                c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+                c => escaped.push(c)
+            }
+        }
+
+        escaped.push('"');
+        escaped
+    }
+
+    /// Standalone SVG drawing, the root on top and the leaves in the order of the tree at the bottom
+    pub fn to_svg(&self) -> String {
+        let no_leaves = self.no_leaves();
+        let max_height = self.linkage.iter().map(|m| m.distance).chain(self.cut.into_iter()).fold(0.0, f64::max);
+        let label_space = if self.labels.is_some() { 80.0 } else { 10.0 };
+        let (left, top, right) = (60.0, 20.0, 20.0);
+        let (plot_width, plot_height) = ((self.width - left - right).max(1.0), (self.height - top - label_space).max(1.0));
+
+        let y = |height: f64| match max_height > 0.0 {
+            true => top + plot_height * (1.0 - height / max_height),
+            false => top + plot_height
+        };
+
+        // Leaves from left to right in the order of the tree, every inner node centered over its children
+        let mut order = Vec::with_capacity(no_leaves);
+        let mut stack = if no_leaves > 0 { vec![self.root()] } else { vec![] };
+        while let Some(node) = stack.pop() {
+            match node < no_leaves {
+                true => order.push(node),
+                false => {
+                    let merge = &self.linkage[node - no_leaves];
+                    stack.push(merge.second);
+                    stack.push(merge.first);
+                }
+            }
+        }
+
+        let mut x = vec![0.0; (2 * no_leaves).saturating_sub(1)];
+        for (position, leaf) in order.iter().enumerate() {
+            x[*leaf] = left + plot_width * (position as f64 + 0.5) / no_leaves as f64;
+        }
+        for (index_m, merge) in self.linkage.iter().enumerate() {
+            x[no_leaves + index_m] = 0.5 * (x[merge.first] + x[merge.second]);
+        }
+
+        let mut svg = String::new();
+        writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">", self.width, self.height, self.width, self.height).unwrap();
+        svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n");
+
+        // Height axis with five ticks
+        writeln!(svg, "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"black\"/>", left - 10.0, y(0.0), left - 10.0, y(max_height)).unwrap();
+        for tick in 0..5 {
+            let height = max_height * tick as f64 / 4.0;
+            writeln!(svg, "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"10\" text-anchor=\"end\" dominant-baseline=\"middle\">{:.3}</text>", left - 14.0, y(height), height).unwrap();
+        }
+
+        svg.push_str("<g stroke=\"black\" fill=\"none\">\n");
+        for merge in self.linkage.iter() {
+            let (x1, x2) = (x[merge.first], x[merge.second]);
+            let (y1, y2, y_merge) = (y(self.node_height(merge.first)), y(self.node_height(merge.second)), y(merge.distance));
+
+            writeln!(svg, "<path d=\"M{:.2},{:.2}V{:.2}H{:.2}V{:.2}\"/>", x1, y1, y_merge, x2, y2).unwrap();
+        }
+        svg.push_str("</g>\n");
+
+        if let Some(ref labels) = self.labels {
+            for (leaf, label) in labels.iter().enumerate().filter(|&(leaf, _)| leaf < no_leaves) {
+                writeln!(svg, "<text x=\"{:.2}\" y=\"{:.2}\" font-size=\"10\" text-anchor=\"end\" transform=\"rotate(-90 {:.2} {:.2})\">{}</text>",
+                       x[leaf], y(0.0) + 6.0, x[leaf], y(0.0) + 6.0, Self::xml_text(label)).unwrap();
+            }
+        }
+
+        if let Some(cut) = self.cut {
+            writeln!(svg, "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"red\" stroke-dasharray=\"6,4\"/>", left, y(cut), left + plot_width, y(cut)).unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    fn xml_text(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+    }
+
+    /// Leaf names, the leaf numbers if not set
+    pub fn set_labels(self, labels: Option<Vec<String>>) -> Self {
+        Dendrogram { labels, .. self }
+    }
+
+    /// Height of a horizontal line drawn across the SVG, showing where the tree is cut
+    pub fn set_cut(self, cut: Option<f64>) -> Self {
+        Dendrogram { cut, .. self }
+    }
+
+    /// Size of the SVG in pixels
+    pub fn set_size(self, width: f64, height: f64) -> Self {
+        Dendrogram { width, height, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use point::Point;
+    use clustering::agglomerative::{Agglomerative, Link};
+
+    fn dendrogram(values: &[f64]) -> Dendrogram {
+        let points: Vec<Point> = values.iter().map(|x| Point::new(vec![*x])).collect();
+        Agglomerative::new().set_link(Link::Single).run(&points, 1).dendrogram()
+    }
+
+    #[test]
+    fn can_export_newick() {
+        assert_eq!("((3:1,4:1):3,(2:2,(0:1,1:1):1):2);", dendrogram(&[0.0, 1.0, 3.0, 7.0, 8.0]).to_newick());
+
+        let labels = vec!["a b".to_string(), "it's".to_string(), "c".to_string()];
+        assert_eq!("(c:2,('a b':1,'it''s':1):1);", dendrogram(&[0.0, 1.0, 3.0]).set_labels(Some(labels)).to_newick());
+    }
+
+    #[test]
+    fn can_export_json() {
+        let labels = vec!["a".to_string(), "b\"".to_string(), "c".to_string()];
+
+        assert_eq!(
+            "{\"id\":4,\"height\":2,\"size\":3,\"children\":[{\"id\":2,\"label\":\"c\",\"height\":0,\"size\":1},\
+             {\"id\":3,\"height\":1,\"size\":2,\"children\":[{\"id\":0,\"label\":\"a\",\"height\":0,\"size\":1},{\"id\":1,\"label\":\"b\\\"\",\"height\":0,\"size\":1}]}]}",
+            dendrogram(&[0.0, 1.0, 3.0]).set_labels(Some(labels)).to_json()
+        );
+    }
+
+    #[test]
+    fn can_export_svg() {
+        let labels: Vec<String> = (0..5).map(|i| format!("<{}>", i)).collect();
+        let svg = dendrogram(&[0.0, 1.0, 3.0, 7.0, 8.0]).set_labels(Some(labels)).set_cut(Some(3.0)).to_svg();
+
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(4, svg.matches("<path").count());
+        assert_eq!(5, svg.matches("&lt;").count());
+        assert!(svg.contains("stroke-dasharray"));
+    }
+
+    #[test]
+    fn can_export_empty_trees() {
+        let empty = Agglomerative::new().run(&[], 1).dendrogram();
+
+        assert_eq!(";", empty.to_newick());
+        assert_eq!("null", empty.to_json());
+        assert_eq!(0, empty.to_svg().matches("<path").count());
+        assert_eq!("0;", dendrogram(&[0.0]).to_newick());
+    }
+
+    #[test]
+    fn writes_infinite_heights_as_null() {
+        let linkage = vec![Merge { first: 0, second: 1, distance: f64::INFINITY, size: 2 }];
+
+        assert!(Dendrogram::new(&linkage, 2).to_json().starts_with("{\"id\":2,\"height\":null,"));
+    }
+
+    #[test]
+    fn can_export_deep_trees() {
+        // Every point joins the cluster of all points before it
+        let linkage: Vec<Merge> = (1..20000).map(|i| {
+            Merge { first: if i == 1 { 0 } else { 20000 + i - 2 }, second: i, distance: i as f64, size: i + 1 }
+        }).collect();
+        let newick = Dendrogram::new(&linkage, 20000).to_newick();
+
+        assert_eq!(19999, newick.matches('(').count());
+    }
+}
@@ -6,16 +6,12 @@ use rand::Rng;
 use rand::distributions::{IndependentSample, Range};
 
 use std::cmp::Ordering;
-use std::usize;
 use std::f64;
 use point::Point;
 use clustering::fuzzy_cmeans::FuzzyCMeansInitialization::*;
-use statistics::distance::{Distance, SquaredEuclidean};
-use statistics::statistics::Statistics;
+use statistics::distance::{Distance, Euclidean, SquaredEuclidean};
 use rayon::prelude::*;
 
-use std::sync::{Mutex};
-
 #[derive(Copy, Clone, Debug)]
 pub enum FuzzyCMeansInitialization {
     Random,
@@ -24,79 +20,104 @@ pub enum FuzzyCMeansInitialization {
 }
 
 pub struct FuzzyCMeans {
-    assignments: Vec<Vec<f64>>,
+    memberships: Vec<Vec<f64>>,
+    assignments: Vec<usize>,
     centroids: Vec<Point>,
+    objective: f64,
     iterations: usize,
-    converged: bool
+    converged: bool,
+    init_method: FuzzyCMeansInitialization,
+    precomputed: Option<Vec<Vec<f64>>>,
+    fuzziness: f64,
+    max_iterations: usize,
+    tolerance: f64
+}
+
+impl Default for FuzzyCMeans {
+    fn default() -> FuzzyCMeans {
+        FuzzyCMeans {
+            memberships: vec![],
+            assignments: vec![],
+            centroids: vec![],
+            objective: 0.0,
+            iterations: 0,
+            converged: false,
+            init_method: FuzzyCMeansPlusPlus,
+            precomputed: None,
+            fuzziness: 2.0,
+            max_iterations: 100,
+            tolerance: 0.00001
+        }
+    }
 }
 
 impl FuzzyCMeans {
-    pub fn run(points: &[Point], no_clusters: usize, max_iterations: usize, fuzziness: f64, epsilon: f64, init_method: FuzzyCMeansInitialization, precomputed: Option<&[Vec<f64>]>) -> Self {
-        if fuzziness <= 1.0 {
-            panic!()
+    pub fn new() -> Self {
+        FuzzyCMeans::default()
+    }
+
+    pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
+        if self.fuzziness <= 1.0 {
+            panic!("Expected a fuzziness above 1, found {}", self.fuzziness)
         }
 
-        if epsilon <= 0.0 || epsilon > 1.0 {
-            panic!()
+        if points.is_empty() || no_clusters == 0 {
+            return FuzzyCMeans { memberships: vec![], assignments: vec![], centroids: vec![], objective: 0.0, iterations: 0, converged: true, .. self }
         }
 
         let dimension = points[0].coordinates().len();
+        let fuzziness = self.fuzziness;
 
-        let mut centroids = Self::initial_centroids(points, no_clusters, init_method, precomputed);
-
-        let mut previous_round: Vec<Vec<f64>> = points.par_iter().map(|p| Self::memberships(p.coordinates(), &centroids, fuzziness)).collect();
+        let mut centroids = self.initial_centroids(points, no_clusters);
+        let mut memberships: Vec<Vec<f64>> = points.par_iter().map(|p| Self::memberships_of(p.coordinates(), &centroids, fuzziness)).collect();
 
         let mut i = 0;
 
-        while i < max_iterations {
-            let max_delta = Mutex::new(f64::NEG_INFINITY);
-
-            previous_round = points.par_iter().zip(previous_round.par_iter()).map(|(p, previous_memberships)| {
-                let memberships = Self::memberships(p.coordinates(), centroids.as_slice(), fuzziness);
-
-                let delta = SquaredEuclidean::distance(&memberships, previous_memberships);
-                let mut max_delta = max_delta.lock().unwrap();
-                if delta > *max_delta {
-                    *max_delta = delta;
-                }
+        while i < self.max_iterations {
+            centroids = memberships.iter().zip(points.iter()).fold(vec![(vec![0.0; dimension], 0.0); centroids.len()], |mut clusters, (point_memberships, point)| {
+                for (cluster, membership) in clusters.iter_mut().zip(point_memberships.iter()) {
+                    let weight = membership.powf(fuzziness);
 
-                memberships
-            }).collect();
-
-            if *max_delta.lock().unwrap() < epsilon {
-                break;
-            }
-
-            centroids = previous_round.iter().zip(points.iter()).fold(vec![vec![(0.0, 0.0); dimension]; no_clusters], |mut clusters, (memberships, point)| {
-                let coordinates = point.coordinates();
-
-                for i in 0..clusters.len() {
-                    let membership = memberships[i].powf(fuzziness);
-
-                    for j in 0..dimension {
-                        clusters[i][j] = (clusters[i][j].0 + (membership * coordinates[j]), clusters[i][j].1 + membership);
+                    for (sum, x) in cluster.0.iter_mut().zip(point.coordinates().iter()) {
+                        *sum += weight * x;
                     }
+                    cluster.1 += weight;
                 }
 
                 clusters
-            })
-            .into_iter()
-            .map(|cluster| cluster.iter().map(|&(numerator, denominator)| numerator / denominator).collect())
-            .collect();
+            }).into_iter().zip(centroids.iter()).map(|((sum, weight), previous)| match weight > 0.0 {
+                true => sum.iter().map(|s| s / weight).collect(),
+                false => previous.clone()
+            }).collect();
+
+            let updated: Vec<Vec<f64>> = points.par_iter().map(|p| Self::memberships_of(p.coordinates(), &centroids, fuzziness)).collect();
+            let max_delta = updated.par_iter().zip(memberships.par_iter()).map(|(u, previous)| SquaredEuclidean::distance(u, previous)).reduce(|| 0.0, f64::max);
 
+            memberships = updated;
             i += 1;
+
+            if max_delta < self.tolerance * self.tolerance {
+                break;
+            }
         }
 
+        let objective = points.par_iter().zip(memberships.par_iter()).map(|(p, u)| {
+            centroids.iter().zip(u.iter()).map(|(c, membership)| membership.powf(fuzziness) * SquaredEuclidean::distance(p.coordinates(), c)).sum::<f64>()
+        }).sum();
+
         FuzzyCMeans {
-            assignments: previous_round,
-            centroids: centroids.into_iter().map(|c| Point::new(c)).collect(),
+            assignments: memberships.iter().map(|u| Self::largest(u).0).collect(),
+            memberships,
+            centroids: centroids.into_iter().map(Point::new).collect(),
+            objective,
             iterations: i,
-            converged: i < max_iterations
+            converged: i < self.max_iterations,
+            .. self
         }
     }
 
-    fn initial_centroids(points: &[Point], no_clusters: usize, init_method: FuzzyCMeansInitialization, precomputed: Option<&[Vec<f64>]>) -> Vec<Vec<f64>> {
-        match init_method {
+    fn initial_centroids(&self, points: &[Point], no_clusters: usize) -> Vec<Vec<f64>> {
+        match self.init_method {
             Random => {
                 let mut rng = rand::thread_rng();
                 let between = Range::new(0, points.len());
@@ -112,15 +133,10 @@ impl FuzzyCMeans {
                 let mut centroids: Vec<Vec<f64>> = vec![points[between.ind_sample(&mut rng)].coordinates().to_vec()];
 
                 for _ in 1..no_clusters {
-                    let mut sum = 0.0;
-                    let distances: Vec<(usize, f64)> = points.iter().enumerate().map(|(index_p, p)| {
-                        let (_, distance_c) = Self::closest_centroid(p.coordinates(), centroids.as_slice());
-                        sum + distance_c;
-                        (index_p, distance_c)
-                    }).collect();
-
-                    sum *= rng.next_f64();
-                    for (index_p, d) in distances.into_iter() {
+                    let distances: Vec<f64> = points.iter().map(|p| Self::closest_centroid(p.coordinates(), centroids.as_slice()).1).collect();
+                    let mut sum = distances.iter().sum::<f64>() * rng.next_f64();
+
+                    for (index_p, d) in distances.into_iter().enumerate() {
                         sum -= d;
 
                         if sum <= 0f64 {
@@ -133,7 +149,17 @@ impl FuzzyCMeans {
                 centroids
             },
             Precomputed => {
-                precomputed.expect("Expected a slice of clusters, on the form Vec<f64>").to_vec()
+                let centroids = self.precomputed.clone().expect("Expected a vec of clusters, on the form Vec<f64>");
+                if centroids.len() != no_clusters {
+                    panic!("Expected {} precomputed centroids, found {}", no_clusters, centroids.len())
+                }
+
+                let dimension = points[0].coordinates().len();
+                if let Some(c) = centroids.iter().find(|c| c.len() != dimension) {
+                    panic!("Expected precomputed centroids of dimension {}, found {}", dimension, c.len())
+                }
+
+                centroids
             }
         }
     }
@@ -150,40 +176,208 @@ impl FuzzyCMeans {
         }
     }
 
+    // With squared distances the exponent 2 / (m - 1) becomes 1 / (m - 1). A point on a centroid belongs to it alone.
     #[inline]
-    fn memberships(point: &[f64], centroids: &[Vec<f64>], fuzziness: f64) -> Vec<f64> {
-        centroids.iter().map(|c_j| {
-            let distance = SquaredEuclidean::distance(point, c_j);
-            let total_distance = centroids.iter().map(|c_k| {
-                (distance / SquaredEuclidean::distance(point, c_k)).powf(2.0 / (fuzziness - 1.0))
-            }).sum::<f64>();
-
-            1.0 / total_distance
+    fn memberships_of(point: &[f64], centroids: &[Vec<f64>], fuzziness: f64) -> Vec<f64> {
+        let distances: Vec<f64> = centroids.iter().map(|c| SquaredEuclidean::distance(point, c)).collect();
+        let no_zero = distances.iter().filter(|d| **d == 0.0).count();
+
+        if no_zero > 0 {
+            return distances.iter().map(|d| if *d == 0.0 { 1.0 / no_zero as f64 } else { 0.0 }).collect()
+        }
+
+        distances.iter().map(|d_j| {
+            1.0 / distances.iter().map(|d_k| (d_j / d_k).powf(1.0 / (fuzziness - 1.0))).sum::<f64>()
         }).collect()
     }
 
+    #[inline]
+    fn largest(memberships: &[f64]) -> (usize, f64) {
+        memberships.iter().cloned().enumerate().max_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal)).unwrap_or((0, 0.0))
+    }
+
+    /// Memberships of every point, summing to 1 over the clusters
+    pub fn memberships(&self) -> &[Vec<f64>] { &self.memberships }
+
+    /// Cluster of largest membership of every point
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
     pub fn centroids(&self) -> &[Point] {
         &self.centroids
     }
+
+    /// Sum of the squared distances to every centroid, weighted by the memberships raised to the fuzziness
+    pub fn objective(&self) -> f64 { self.objective }
+
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    /// Mean of the squared memberships, from 1 / c for a uniform partition up to 1 for a crisp one
+    pub fn partition_coefficient(&self) -> f64 {
+        self.memberships.iter().flat_map(|u| u.iter()).map(|u| u * u).sum::<f64>() / self.memberships.len() as f64
+    }
+
+    /// Mean entropy of the memberships, from 0 for a crisp partition up to ln(c) for a uniform one
+    pub fn partition_entropy(&self) -> f64 {
+        -self.memberships.iter().flat_map(|u| u.iter()).filter(|u| **u > 0.0).map(|u| u * u.ln()).sum::<f64>() / self.memberships.len() as f64
+    }
+
+    /// Compactness over separation, the objective divided by n times the smallest squared distance between centroids. Lower is better,
+    /// and infinite with fewer than two centroids.
+    pub fn xie_beni(&self) -> f64 {
+        if self.centroids.len() < 2 {
+            return f64::INFINITY
+        }
+
+        let separation = self.centroids.iter().enumerate().flat_map(|(index_c, c)| {
+            self.centroids.iter().skip(index_c + 1).map(move |other| SquaredEuclidean::distance(c.coordinates(), other.coordinates()))
+        }).fold(f64::INFINITY, f64::min);
+
+        self.objective / (self.memberships.len() as f64 * separation)
+    }
+
+    /// Silhouette of the hard labels, every point weighted by the gap between its two largest memberships raised to `alpha`.
+    /// Higher is better.
+    pub fn fuzzy_silhouette(&self, points: &[Point], alpha: f64) -> f64 {
+        if points.len() != self.assignments.len() {
+            panic!("Expected the {} clustered points, found {}", self.assignments.len(), points.len())
+        }
+
+        let no_clusters = self.centroids.len();
+        let assignments = &self.assignments;
+        let sizes = assignments.iter().fold(vec![0usize; no_clusters], |mut sizes, a| {
+            sizes[*a] += 1;
+            sizes
+        });
+
+        let (weighted, weights) = points.par_iter().enumerate().map(|(index_p, p)| {
+            let sums = points.iter().zip(assignments.iter()).fold(vec![0.0; no_clusters], |mut sums, (q, a)| {
+                sums[*a] += Euclidean::distance(p.coordinates(), q.coordinates());
+                sums
+            });
+
+            let own = assignments[index_p];
+            let silhouette = match sizes[own] > 1 {
+                true => {
+                    let a = sums[own] / (sizes[own] - 1) as f64;
+                    let b = (0..no_clusters).filter(|index_c| *index_c != own && sizes[*index_c] > 0).map(|index_c| sums[index_c] / sizes[index_c] as f64).fold(f64::INFINITY, f64::min);
+
+                    if b.is_finite() && a.max(b) > 0.0 { (b - a) / a.max(b) } else { 0.0 }
+                },
+                false => 0.0
+            };
+
+            let mut u = self.memberships[index_p].clone();
+            u.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+            let weight = (u[0] - u.get(1).cloned().unwrap_or(0.0)).powf(alpha);
+
+            (weight * silhouette, weight)
+        }).reduce(|| (0.0, 0.0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+        if weights > 0.0 { weighted / weights } else { 0.0 }
+    }
+
+    /// Exponent m of the memberships, above 1, larger values giving fuzzier clusters
+    pub fn set_fuzziness(self, fuzziness: f64) -> Self {
+        FuzzyCMeans { fuzziness, .. self }
+    }
+
+    pub fn set_tolerance(self, tolerance: f64) -> Self {
+        FuzzyCMeans { tolerance, .. self }
+    }
+
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        FuzzyCMeans { max_iterations, .. self }
+    }
+
+    pub fn set_init_method(self, init_method: FuzzyCMeansInitialization) -> Self {
+        FuzzyCMeans { init_method, .. self }
+    }
+
+    pub fn set_precomputed(self, precomputed: &Option<Vec<Vec<f64>>>) -> Self {
+        FuzzyCMeans { precomputed: precomputed.clone(), .. self }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand;
-    use rand::Rng;
-    use time;
-
-    /*#[test]
-    fn can_run() {
-        let expected: Vec<KMeansCluster> = vec![
-            KMeansCluster::from(vec![Point::new(vec![0.0, 1.0]), Point::new(vec![1.0, 2.0]), Point::new(vec![2.0, 3.0])]),
-            KMeansCluster::from(vec![Point::new(vec![3.0, 4.0]), Point::new(vec![4.0, 5.0])])
-        ];
-        let mut input = vec![Point::new(vec![0.0, 1.0]), Point::new(vec![1.0, 2.0]), Point::new(vec![2.0, 3.0]), Point::new(vec![3.0, 4.0]), Point::new(vec![4.0, 5.0])];
-
-        let output = kmeans(input.as_mut_slice(), 2, usize::max_value());
-
-        assert_eq!(expected, output);
-    }*/
-}
\ No newline at end of file
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_fuzzy_cmeans() {
+        let points = blobs();
+        let output = FuzzyCMeans::new().run(&points, 3);
+
+        assert!(output.converged());
+        assert_eq!(3, output.centroids().len());
+        assert!(output.memberships().iter().all(|u| (u.iter().sum::<f64>() - 1.0).abs() < 1e-9));
+        for blob in 0..3 {
+            assert!(output.assignments()[blob * 100..(blob + 1) * 100].iter().all(|a| *a == output.assignments()[blob * 100]));
+        }
+
+        assert!(output.partition_coefficient() > 1.0 / 3.0 && output.partition_coefficient() <= 1.0);
+        assert!(output.partition_entropy() >= 0.0 && output.partition_entropy() < 3f64.ln());
+    }
+
+    #[test]
+    fn validity_indices_pick_number_of_clusters() {
+        let points = blobs();
+        let outputs: Vec<FuzzyCMeans> = (1..6).map(|c| FuzzyCMeans::new().run(&points, c)).collect();
+
+        let best_xie_beni = (0..5).min_by(|a, b| outputs[*a].xie_beni().partial_cmp(&outputs[*b].xie_beni()).unwrap()).unwrap();
+        let best_silhouette = (0..5).max_by(|a, b| {
+            outputs[*a].fuzzy_silhouette(&points, 1.0).partial_cmp(&outputs[*b].fuzzy_silhouette(&points, 1.0)).unwrap()
+        }).unwrap();
+
+        assert_eq!(2, best_xie_beni);
+        assert_eq!(2, best_silhouette);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 3 precomputed centroids, found 2")]
+    fn precomputed_centroids_match_number_of_clusters() {
+        let centroids = Some(vec![vec![0.0, 0.0], vec![5.0, 5.0]]);
+        FuzzyCMeans::new().set_init_method(Precomputed).set_precomputed(&centroids).run(&blobs(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected precomputed centroids of dimension 2, found 3")]
+    fn precomputed_centroids_match_dimension() {
+        let centroids = Some(vec![vec![0.0, 0.0], vec![5.0, 5.0], vec![-5.0, 5.0, 0.0]]);
+        FuzzyCMeans::new().set_init_method(Precomputed).set_precomputed(&centroids).run(&blobs(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected the 300 clustered points, found 100")]
+    fn fuzzy_silhouette_needs_clustered_points() {
+        let points = blobs();
+        FuzzyCMeans::new().run(&points, 3).fuzzy_silhouette(&points[..100], 1.0);
+    }
+
+    #[test]
+    fn can_run_without_points_or_clusters() {
+        for init_method in [Random, FuzzyCMeansPlusPlus].iter() {
+            let output = FuzzyCMeans::new().set_init_method(*init_method).run(&blobs(), 0);
+            assert!(output.centroids().is_empty());
+            assert!(output.memberships().is_empty());
+            assert!(output.assignments().is_empty());
+
+            let output = FuzzyCMeans::new().set_init_method(*init_method).run(&[], 3);
+            assert!(output.centroids().is_empty());
+            assert_eq!(0.0, output.objective());
+        }
+    }
+
+    #[test]
+    fn point_on_centroid_belongs_to_it() {
+        let memberships = FuzzyCMeans::memberships_of(&[1.0, 1.0], &[vec![0.0, 0.0], vec![1.0, 1.0]], 2.0);
+
+        assert_eq!(vec![0.0, 1.0], memberships);
+    }
+}
@@ -0,0 +1,470 @@
+// https://www.ics.uci.edu/~smyth/courses/cs274/notes/EMnotes.pdf
+
+use rand;
+use rand::Rng;
+
+use std::cmp::Ordering;
+use std::usize;
+use std::f64;
+use std::f64::consts::PI;
+use point::Point;
+use clustering::kmeans::{KMeans, KMeansInitialization};
+use clustering::gaussian_mixture::CovarianceType::*;
+use clustering::gaussian_mixture::GaussianMixtureInitialization::*;
+use statistics::statistics::Statistics;
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum CovarianceType {
+    /// Every component has its own general covariance matrix
+    Full,
+    /// Every component has its own diagonal covariance matrix
+    Diagonal,
+    /// Every component has its own single variance
+    Spherical,
+    /// All components share the same general covariance matrix
+    Tied
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum GaussianMixtureInitialization {
+    /// Responsibilities from a KMeans++ clustering
+    KMeansCentroids,
+    Random
+}
+
+pub struct GaussianMixture {
+    assignments: Vec<usize>,
+    weights: Vec<f64>,
+    means: Vec<Point>,
+    covariances: Vec<Vec<Vec<f64>>>,
+    cholesky_factors: Vec<Vec<Vec<f64>>>,
+    lower_bound: f64,
+    iterations: usize,
+    converged: bool,
+    covariance_type: CovarianceType,
+    init_method: GaussianMixtureInitialization,
+    max_iterations: usize,
+    tolerance: f64,
+    regularization: f64
+}
+
+impl Default for GaussianMixture {
+    fn default() -> GaussianMixture {
+        GaussianMixture {
+            assignments: vec![],
+            weights: vec![],
+            means: vec![],
+            covariances: vec![],
+            cholesky_factors: vec![],
+            lower_bound: f64::NEG_INFINITY,
+            iterations: 0,
+            converged: false,
+            covariance_type: Full,
+            init_method: KMeansCentroids,
+            max_iterations: 100,
+            tolerance: 0.001,
+            regularization: 0.000001
+        }
+    }
+}
+
+impl GaussianMixture {
+    pub fn new() -> Self {
+        GaussianMixture::default()
+    }
+
+    pub fn run(self, points: &[Point], no_components: usize) -> Self {
+        if points.is_empty() || no_components == 0 {
+            return GaussianMixture {
+                assignments: vec![],
+                weights: vec![],
+                means: vec![],
+                covariances: vec![],
+                cholesky_factors: vec![],
+                iterations: 0,
+                converged: true,
+                .. self
+            }
+        }
+
+        let mut model = self.maximization(points, &self.initial_responsibilities(points, no_components));
+        let mut lower_bound = f64::NEG_INFINITY;
+
+        let mut i = 0;
+        let mut converged = false;
+
+        while i < self.max_iterations {
+            let (log_responsibilities, log_likelihoods) = model.expectation(points);
+
+            let updated_lower_bound = log_likelihoods.iter().sum::<f64>() / points.len() as f64;
+            let responsibilities: Vec<Vec<f64>> = log_responsibilities.into_iter().map(|r| r.into_iter().map(|x| x.exp()).collect()).collect();
+
+            model = model.maximization(points, &responsibilities);
+
+            i += 1;
+
+            let change = (updated_lower_bound - lower_bound).abs();
+            lower_bound = updated_lower_bound;
+            if change < self.tolerance {
+                converged = true;
+                break;
+            }
+        }
+
+        let assignments = model.predict(points);
+        let (_, log_likelihoods) = model.expectation(points);
+
+        GaussianMixture {
+            assignments,
+            lower_bound: log_likelihoods.iter().sum::<f64>() / points.len() as f64,
+            iterations: i,
+            converged,
+            .. model
+        }
+    }
+
+    fn initial_responsibilities(&self, points: &[Point], no_components: usize) -> Vec<Vec<f64>> {
+        match self.init_method {
+            KMeansCentroids => {
+                let kmeans = KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus).run(points, no_components);
+
+                kmeans.assignments().iter().map(|a| {
+                    (0..no_components).map(|index_c| if index_c == *a { 1.0 } else { 0.0 }).collect()
+                }).collect()
+            },
+            Random => {
+                let mut rng = rand::thread_rng();
+
+                points.iter().map(|_| {
+                    let responsibilities: Vec<f64> = (0..no_components).map(|_| rng.next_f64()).collect();
+                    let total = responsibilities.iter().sum::<f64>();
+                    responsibilities.into_iter().map(|r| r / total).collect()
+                }).collect()
+            }
+        }
+    }
+
+    /// M-step: weights, means and covariances maximizing the expected log-likelihood
+    fn maximization(&self, points: &[Point], responsibilities: &[Vec<f64>]) -> Self {
+        let n = points.len();
+        let dimension = points[0].coordinates().len();
+        let no_components = responsibilities[0].len();
+
+        let totals: Vec<f64> = (0..no_components).map(|index_c| {
+            responsibilities.iter().map(|r| r[index_c]).sum::<f64>() + 10.0 * f64::EPSILON
+        }).collect();
+
+        let means: Vec<Vec<f64>> = (0..no_components).map(|index_c| {
+            points.iter().zip(responsibilities.iter()).fold(vec![0.0; dimension], |mut mean, (p, r)| {
+                for (m, x) in mean.iter_mut().zip(p.coordinates().iter()) {
+                    *m += r[index_c] * x;
+                }
+
+                mean
+            }).into_iter().map(|m| m / totals[index_c]).collect()
+        }).collect();
+
+        let scatter = |index_c: usize| -> Vec<Vec<f64>> {
+            let mut covariance = vec![vec![0.0; dimension]; dimension];
+
+            for (p, r) in points.iter().zip(responsibilities.iter()) {
+                let difference: Vec<f64> = p.coordinates().iter().zip(means[index_c].iter()).map(|(x, m)| x - m).collect();
+
+                for i in 0..dimension {
+                    for j in 0..(i + 1) {
+                        covariance[i][j] += r[index_c] * difference[i] * difference[j];
+                    }
+                }
+            }
+
+            for i in 0..dimension {
+                for j in 0..i {
+                    covariance[j][i] = covariance[i][j];
+                }
+            }
+
+            covariance
+        };
+
+        let mut covariances: Vec<Vec<Vec<f64>>> = match self.covariance_type {
+            Tied => {
+                let shared = (0..no_components).map(|index_c| scatter(index_c)).fold(vec![vec![0.0; dimension]; dimension], |mut shared, covariance| {
+                    for i in 0..dimension {
+                        for j in 0..dimension {
+                            shared[i][j] += covariance[i][j] / n as f64;
+                        }
+                    }
+
+                    shared
+                });
+
+                vec![shared; no_components]
+            },
+            _ => (0..no_components).into_par_iter().map(|index_c| {
+                scatter(index_c).into_iter().map(|row| row.into_iter().map(|x| x / totals[index_c]).collect()).collect()
+            }).collect()
+        };
+
+        for covariance in covariances.iter_mut() {
+            match self.covariance_type {
+                Diagonal => {
+                    for i in 0..dimension {
+                        for j in 0..dimension {
+                            if i != j {
+                                covariance[i][j] = 0.0;
+                            }
+                        }
+                    }
+                },
+                Spherical => {
+                    let variance = (0..dimension).map(|i| covariance[i][i]).sum::<f64>() / dimension as f64;
+
+                    for i in 0..dimension {
+                        for j in 0..dimension {
+                            covariance[i][j] = if i == j { variance } else { 0.0 };
+                        }
+                    }
+                },
+                Full | Tied => ()
+            }
+
+            for i in 0..dimension {
+                covariance[i][i] += self.regularization;
+            }
+        }
+
+        let cholesky_factors = covariances.iter_mut().map(|covariance| Self::regularized_cholesky(covariance)).collect();
+
+        GaussianMixture {
+            weights: totals.iter().map(|t| t / n as f64).collect(),
+            means: means.into_iter().map(|m| Point::new(m)).collect(),
+            covariances,
+            cholesky_factors,
+            assignments: vec![],
+            lower_bound: self.lower_bound,
+            iterations: self.iterations,
+            converged: self.converged,
+            covariance_type: self.covariance_type,
+            init_method: self.init_method,
+            max_iterations: self.max_iterations,
+            tolerance: self.tolerance,
+            regularization: self.regularization
+        }
+    }
+
+    /// Cholesky factor of the covariance matrix. A singular matrix, e.g. of a component with fewer points than
+    /// dimensions, gets an increasing multiple of its largest variance added to its diagonal until it is positive definite
+    fn regularized_cholesky(covariance: &mut Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+        let dimension = covariance.len();
+        let scale = (0..dimension).map(|i| covariance[i][i].abs()).fold(f64::MIN_POSITIVE, f64::max);
+        let mut jitter = scale * f64::EPSILON;
+
+        loop {
+            if let Some(lower) = Statistics::cholesky(covariance) {
+                return lower
+            }
+
+            if !jitter.is_finite() {
+                panic!("Expected a finite covariance matrix, found {:?}", covariance)
+            }
+
+            for i in 0..dimension {
+                covariance[i][i] += jitter;
+            }
+            jitter *= 10.0;
+        }
+    }
+
+    /// E-step: log-responsibilities of every component and log-likelihood of every point
+    fn expectation(&self, points: &[Point]) -> (Vec<Vec<f64>>, Vec<f64>) {
+        points.par_iter().map(|p| {
+            let weighted = self.weighted_log_probabilities(p.coordinates());
+            let log_likelihood = Statistics::log_sum_exp(&weighted);
+
+            (weighted.into_iter().map(|w| w - log_likelihood).collect(), log_likelihood)
+        }).unzip()
+    }
+
+    #[inline]
+    fn weighted_log_probabilities(&self, point: &[f64]) -> Vec<f64> {
+        let dimension = point.len() as f64;
+
+        self.weights.iter().zip(self.means.iter()).zip(self.cholesky_factors.iter()).map(|((weight, mean), lower)| {
+            let difference: Vec<f64> = point.iter().zip(mean.coordinates().iter()).map(|(x, m)| x - m).collect();
+            let mahalanobis = Statistics::forward_substitution(lower, &difference).iter().map(|z| z * z).sum::<f64>();
+            let log_determinant = 2.0 * (0..lower.len()).map(|i| lower[i][i].ln()).sum::<f64>();
+
+            weight.ln() - 0.5 * (dimension * (2.0 * PI).ln() + log_determinant + mahalanobis)
+        }).collect()
+    }
+
+    /// Posterior probability of every component for every point
+    pub fn predict_proba(&self, points: &[Point]) -> Vec<Vec<f64>> {
+        self.expectation(points).0.into_iter().map(|r| r.into_iter().map(|x| x.exp()).collect()).collect()
+    }
+
+    pub fn predict(&self, points: &[Point]) -> Vec<usize> {
+        points.par_iter().map(|p| {
+            match self.weighted_log_probabilities(p.coordinates()).into_iter().enumerate().max_by(|&(_, a), &(_, b)| {
+                a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+            }) {
+                Some((index_c, _)) => index_c,
+                None => panic!()
+            }
+        }).collect()
+    }
+
+    /// Log-likelihood of every point under the model
+    pub fn score_samples(&self, points: &[Point]) -> Vec<f64> {
+        self.expectation(points).1
+    }
+
+    /// Average log-likelihood of the points under the model
+    pub fn score(&self, points: &[Point]) -> f64 {
+        self.score_samples(points).iter().sum::<f64>() / points.len() as f64
+    }
+
+    /// Bayesian information criterion, lower is better
+    pub fn bic(&self, points: &[Point]) -> f64 {
+        let n = points.len() as f64;
+        -2.0 * self.score(points) * n + self.no_parameters() as f64 * n.ln()
+    }
+
+    /// Akaike information criterion, lower is better
+    pub fn aic(&self, points: &[Point]) -> f64 {
+        let n = points.len() as f64;
+        -2.0 * self.score(points) * n + 2.0 * self.no_parameters() as f64
+    }
+
+    fn no_parameters(&self) -> usize {
+        let k = self.weights.len();
+        let d = match self.means.first() {
+            Some(mean) => mean.coordinates().len(),
+            None => 0
+        };
+
+        let covariance_parameters = match self.covariance_type {
+            Full => k * d * (d + 1) / 2,
+            Diagonal => k * d,
+            Spherical => k,
+            Tied => d * (d + 1) / 2
+        };
+
+        covariance_parameters + k * d + k - 1
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn weights(&self) -> &[f64] { &self.weights }
+
+    pub fn means(&self) -> &[Point] { &self.means }
+
+    /// Covariance matrix of every component, diagonal and spherical types included
+    pub fn covariances(&self) -> &[Vec<Vec<f64>>] { &self.covariances }
+
+    /// Average log-likelihood of the training points under the fitted model
+    pub fn lower_bound(&self) -> f64 { self.lower_bound }
+
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    pub fn max_iterations(&self) -> usize { self.max_iterations }
+
+    pub fn set_covariance_type(self, covariance_type: CovarianceType) -> Self {
+        GaussianMixture { covariance_type, .. self }
+    }
+
+    pub fn set_init_method(self, init_method: GaussianMixtureInitialization) -> Self {
+        GaussianMixture { init_method, .. self }
+    }
+
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        GaussianMixture { max_iterations, .. self }
+    }
+
+    pub fn set_tolerance(self, tolerance: f64) -> Self {
+        GaussianMixture { tolerance, .. self }
+    }
+
+    /// Non-negative value added to the diagonal of every covariance matrix
+    pub fn set_regularization(self, regularization: f64) -> Self {
+        GaussianMixture { regularization, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::distributions::{IndependentSample, Normal};
+    use datasets::*;
+
+    fn gaussian_blobs() -> Vec<Point> {
+        let mut rng = rand::thread_rng();
+        let narrow = Normal::new(0.0, 0.5);
+        let wide = Normal::new(0.0, 2.0);
+
+        let mut points: Vec<Point> = (0..300).map(|_| Point::new(vec![narrow.ind_sample(&mut rng), wide.ind_sample(&mut rng)])).collect();
+        points.extend((0..300).map(|_| Point::new(vec![10.0 + wide.ind_sample(&mut rng), 10.0 + narrow.ind_sample(&mut rng)])));
+        points
+    }
+
+    #[test]
+    fn can_fit_every_covariance_type() {
+        let points = gaussian_blobs();
+
+        for covariance_type in [Full, Diagonal, Spherical, Tied].iter() {
+            let output = GaussianMixture::new().set_covariance_type(*covariance_type).run(&points, 2);
+
+            assert_eq!(2, output.means().len());
+            assert!((output.weights().iter().sum::<f64>() - 1.0).abs() < 1e-6);
+            assert!(output.assignments()[..300].iter().all(|a| *a == output.assignments()[0]));
+            assert!(output.assignments()[300..].iter().all(|a| *a == output.assignments()[300]));
+            assert!(output.assignments()[0] != output.assignments()[300]);
+        }
+    }
+
+    #[test]
+    fn predict_proba_rows_sum_to_one() {
+        let output = GaussianMixture::new().set_covariance_type(Diagonal).run(iris::load().data(), 3);
+
+        assert!(output.predict_proba(iris::load().data()).iter().all(|r| (r.iter().sum::<f64>() - 1.0).abs() < 1e-6));
+        assert_eq!(iris::load().data().len(), output.score_samples(iris::load().data()).len());
+    }
+
+    #[test]
+    fn bic_prefers_true_number_of_components() {
+        let points = gaussian_blobs();
+
+        let one = GaussianMixture::new().run(&points, 1);
+        let two = GaussianMixture::new().run(&points, 2);
+
+        assert!(two.bic(&points) < one.bic(&points));
+        assert!(two.aic(&points) < one.aic(&points));
+        assert!(two.score(&points) > one.score(&points));
+    }
+
+    #[test]
+    fn regularizes_components_with_fewer_points_than_dimensions() {
+        let points: Vec<Point> = (0..6).map(|i| Point::new(vec![(i / 3) as f64 * 10.0, i as f64, 0.0, 1.0, 2.0])).collect();
+        let output = GaussianMixture::new().set_regularization(0.0).run(&points, 2);
+
+        assert_eq!(2, output.means().len());
+        assert!(output.score(&points).is_finite());
+    }
+
+    #[test]
+    fn can_run_without_points_or_components() {
+        for init_method in [KMeansCentroids, Random].iter() {
+            let output = GaussianMixture::new().set_init_method(*init_method).run(&[], 2);
+            assert!(output.means().is_empty());
+            assert!(output.assignments().is_empty());
+
+            let output = GaussianMixture::new().set_init_method(*init_method).run(&gaussian_blobs(), 0);
+            assert!(output.weights().is_empty());
+            assert!(output.covariances().is_empty());
+        }
+    }
+}
@@ -0,0 +1,429 @@
+// http://link.springer.com/chapter/10.1007%2F978-3-642-37456-2_14
+// https://arxiv.org/pdf/1705.07321.pdf
+
+use std::cmp::Ordering;
+use std::usize;
+use std::f64;
+use std::collections::HashMap;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::NeighborSearch;
+use clustering::hdbscan::ClusterSelection::*;
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum ClusterSelection {
+    /// Picks the clusters of the condensed tree maximizing total stability
+    ExcessOfMass,
+    /// Picks the leaves of the condensed tree, giving many small homogeneous clusters
+    Leaf
+}
+
+/// Edge of the condensed tree. Children below the number of points are points,
+/// the remaining children (and all parents) are clusters, the root being labelled n.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CondensedEdge {
+    pub parent: usize,
+    pub child: usize,
+    pub lambda: f64,
+    pub size: usize
+}
+
+pub struct HDBSCAN {
+    assignments: Vec<Option<usize>>,
+    probabilities: Vec<f64>,
+    outlier_scores: Vec<f64>,
+    condensed_tree: Vec<CondensedEdge>,
+    stabilities: Vec<f64>,
+    min_cluster_size: usize,
+    min_samples: Option<usize>,
+    cluster_selection: ClusterSelection,
+    neighbor_search: NeighborSearch
+}
+
+impl Default for HDBSCAN {
+    fn default() -> HDBSCAN {
+        HDBSCAN {
+            assignments: vec![],
+            probabilities: vec![],
+            outlier_scores: vec![],
+            condensed_tree: vec![],
+            stabilities: vec![],
+            min_cluster_size: 5,
+            min_samples: None,
+            cluster_selection: ExcessOfMass,
+            neighbor_search: NeighborSearch::BruteForce
+        }
+    }
+}
+
+impl HDBSCAN {
+    pub fn new() -> Self {
+        HDBSCAN::default()
+    }
+
+    /// The neighbour search only speeds up the core distances. The minimum spanning tree is built by Prim's algorithm
+    /// over all pairs of points, in O(n²) time and O(n) memory whatever `set_neighbor_search` is
+    pub fn run<D: Distance>(self, points: &[Point]) -> Self {
+        let n = points.len();
+        let min_cluster_size = self.min_cluster_size.max(2);
+
+        if n < 2 {
+            return HDBSCAN {
+                assignments: vec![None; n],
+                probabilities: vec![0.0; n],
+                outlier_scores: vec![0.0; n],
+                .. self
+            }
+        }
+
+        let core_distances = self.core_distances::<D>(points);
+        let hierarchy = Self::single_linkage(Self::minimum_spanning_tree::<D>(points, &core_distances), n);
+        let condensed_tree = Self::condense(&hierarchy, n, min_cluster_size);
+
+        let no_clusters = condensed_tree.iter().map(|e| e.parent).chain(condensed_tree.iter().map(|e| e.child)).max().unwrap_or(n) + 1 - n;
+
+        let mut births = vec![0.0; no_clusters];
+        let mut cluster_parents: Vec<Option<usize>> = vec![None; no_clusters];
+        let mut cluster_children: Vec<Vec<usize>> = vec![vec![]; no_clusters];
+        let mut point_parents = vec![(n, 0.0); n];
+
+        for edge in condensed_tree.iter() {
+            match edge.child >= n {
+                true => {
+                    births[edge.child - n] = edge.lambda;
+                    cluster_parents[edge.child - n] = Some(edge.parent - n);
+                    cluster_children[edge.parent - n].push(edge.child - n);
+                },
+                false => point_parents[edge.child] = (edge.parent, edge.lambda)
+            }
+        }
+
+        let mut stabilities = vec![0.0; no_clusters];
+        // Clusters born at an infinite lambda (duplicate points) gain no stability
+        for edge in condensed_tree.iter().filter(|e| e.lambda > births[e.parent - n]) {
+            stabilities[edge.parent - n] += (edge.lambda - births[edge.parent - n]) * edge.size as f64;
+        }
+
+        let selected = self.select_clusters(&stabilities, &cluster_children);
+
+        // Cluster labels follow the order of the selected clusters in the condensed tree
+        let labels: HashMap<usize, usize> = (0..no_clusters).filter(|c| selected[*c]).enumerate().map(|(label, c)| (c, label)).collect();
+
+        let assignments: Vec<Option<usize>> = point_parents.iter().map(|&(parent, _)| {
+            let mut cluster = Some(parent - n);
+
+            while let Some(c) = cluster {
+                if selected[c] {
+                    return Some(c)
+                }
+
+                cluster = cluster_parents[c];
+            }
+
+            None
+        }).collect();
+
+        // Deepest lambda reached by any point inside the subtree of each cluster
+        let mut deaths = vec![0.0; no_clusters];
+        for &(parent, lambda) in point_parents.iter() {
+            if lambda > deaths[parent - n] {
+                deaths[parent - n] = lambda;
+            }
+        }
+
+        for c in (1..no_clusters).rev() {
+            match cluster_parents[c] {
+                Some(parent) if deaths[c] > deaths[parent] => deaths[parent] = deaths[c],
+                _ => ()
+            }
+        }
+
+        let probabilities = assignments.iter().zip(point_parents.iter()).map(|(assignment, &(_, lambda))| {
+            match *assignment {
+                Some(c) if deaths[c] > 0.0 && deaths[c].is_finite() => lambda.min(deaths[c]) / deaths[c],
+                Some(_) => 1.0,
+                None => 0.0
+            }
+        }).collect();
+
+        let outlier_scores = point_parents.iter().map(|&(parent, lambda)| {
+            let death = deaths[parent - n];
+
+            match death > 0.0 && death.is_finite() {
+                true => (death - lambda) / death,
+                false => 0.0
+            }
+        }).collect();
+
+        HDBSCAN {
+            assignments: assignments.into_iter().map(|a| a.map(|c| labels[&c])).collect(),
+            probabilities,
+            outlier_scores,
+            stabilities: (0..no_clusters).filter(|c| selected[*c]).map(|c| stabilities[c]).collect(),
+            condensed_tree,
+            .. self
+        }
+    }
+
+    /// Distance from every point to its `min_samples`-th nearest neighbour, the point itself included
+    fn core_distances<D: Distance>(&self, points: &[Point]) -> Vec<f64> {
+        let k = self.min_samples.unwrap_or(self.min_cluster_size).max(1).min(points.len());
+        let index = self.neighbor_search.build::<D>(points, f64::INFINITY);
+
+        points.par_iter().map(|p| {
+            match index.nearest(p.coordinates(), k).last() {
+                Some(&(_, distance)) => distance,
+                None => 0.0
+            }
+        }).collect()
+    }
+
+    /// Prim's algorithm over the implicit complete graph of mutual reachability distances
+    fn minimum_spanning_tree<D: Distance>(points: &[Point], core_distances: &[f64]) -> Vec<(usize, usize, f64)> {
+        let n = points.len();
+
+        let mut in_tree = vec![false; n];
+        let mut best: Vec<(usize, f64)> = vec![(0, f64::INFINITY); n];
+        let mut edges = Vec::with_capacity(n - 1);
+        let mut current = 0;
+
+        for _ in 1..n {
+            in_tree[current] = true;
+            let current_coordinates = points[current].coordinates();
+            let current_core = core_distances[current];
+
+            best.par_iter_mut().enumerate().filter(|&(index_p, _)| !in_tree[index_p]).for_each(|(index_p, b)| {
+                let distance = D::distance(current_coordinates, points[index_p].coordinates())
+                                .max(current_core)
+                                .max(core_distances[index_p]);
+
+                if distance < b.1 {
+                    *b = (current, distance);
+                }
+            });
+
+            let (next, &(from, distance)) = best.iter().enumerate().filter(|&(index_p, _)| !in_tree[index_p]).min_by(|&(_, a), &(_, b)| {
+                a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal)
+            }).unwrap();
+
+            edges.push((from, next, distance));
+            current = next;
+        }
+
+        edges
+    }
+
+    /// Turns the spanning tree into merges (left, right, distance, size), new nodes labelled from n upward
+    fn single_linkage(mut edges: Vec<(usize, usize, f64)>, n: usize) -> Vec<(usize, usize, f64, usize)> {
+        edges.sort_by(|a, b| a.2.partial_cmp(&b.2).unwrap_or(Ordering::Equal));
+
+        let mut parents: Vec<usize> = (0..2 * n - 1).collect();
+        let mut sizes: Vec<usize> = vec![1; 2 * n - 1];
+
+        fn find(parents: &mut [usize], mut node: usize) -> usize {
+            while parents[node] != node {
+                parents[node] = parents[parents[node]];
+                node = parents[node];
+            }
+
+            node
+        }
+
+        edges.into_iter().enumerate().map(|(index_e, (a, b, distance))| {
+            let root_a = find(&mut parents, a);
+            let root_b = find(&mut parents, b);
+            let merged = n + index_e;
+
+            parents[root_a] = merged;
+            parents[root_b] = merged;
+            sizes[merged] = sizes[root_a] + sizes[root_b];
+
+            (root_a, root_b, distance, sizes[merged])
+        }).collect()
+    }
+
+    fn condense(hierarchy: &[(usize, usize, f64, usize)], n: usize, min_cluster_size: usize) -> Vec<CondensedEdge> {
+        let size = |node: usize| if node < n { 1 } else { hierarchy[node - n].3 };
+        let leaves = |node: usize| {
+            let mut leaves = vec![];
+            let mut stack = vec![node];
+
+            while let Some(current) = stack.pop() {
+                match current < n {
+                    true => leaves.push(current),
+                    false => {
+                        stack.push(hierarchy[current - n].0);
+                        stack.push(hierarchy[current - n].1);
+                    }
+                }
+            }
+
+            leaves
+        };
+
+        let mut condensed_tree = vec![];
+        let mut next_label = n + 1;
+        let mut stack = vec![(2 * n - 2, n)];
+
+        while let Some((node, label)) = stack.pop() {
+            let (left, right, distance, _) = hierarchy[node - n];
+            let lambda = match distance > 0.0 {
+                true => 1.0 / distance,
+                false => f64::INFINITY
+            };
+
+            match (size(left) >= min_cluster_size, size(right) >= min_cluster_size) {
+                (true, true) => {
+                    for &child in [left, right].iter() {
+                        condensed_tree.push(CondensedEdge { parent: label, child: next_label, lambda, size: size(child) });
+                        stack.push((child, next_label));
+                        next_label += 1;
+                    }
+                },
+                (large_left, large_right) => {
+                    for &(child, large) in [(left, large_left), (right, large_right)].iter() {
+                        match large {
+                            true => stack.push((child, label)),
+                            false => condensed_tree.extend(leaves(child).into_iter().map(|p| {
+                                CondensedEdge { parent: label, child: p, lambda, size: 1 }
+                            }))
+                        }
+                    }
+                }
+            }
+        }
+
+        condensed_tree
+    }
+
+    fn select_clusters(&self, stabilities: &[f64], cluster_children: &[Vec<usize>]) -> Vec<bool> {
+        let no_clusters = stabilities.len();
+        let mut selected = vec![false; no_clusters];
+
+        match self.cluster_selection {
+            ExcessOfMass => {
+                let mut subtree_stabilities = stabilities.to_vec();
+
+                // Children always carry larger labels than their parent, the root (0) is never selected
+                for c in (1..no_clusters).rev() {
+                    let children_stability = cluster_children[c].iter().map(|child| subtree_stabilities[*child]).sum::<f64>();
+
+                    match children_stability > stabilities[c] {
+                        true => subtree_stabilities[c] = children_stability,
+                        false => {
+                            selected[c] = true;
+
+                            let mut descendants = cluster_children[c].clone();
+                            while let Some(descendant) = descendants.pop() {
+                                selected[descendant] = false;
+                                descendants.extend(cluster_children[descendant].iter().cloned());
+                            }
+                        }
+                    }
+                }
+            },
+            Leaf => {
+                for c in 1..no_clusters {
+                    selected[c] = cluster_children[c].is_empty();
+                }
+            }
+        }
+
+        selected
+    }
+
+    /// Cluster of every point, `None` for noise
+    pub fn assignments(&self) -> &[Option<usize>] { &self.assignments }
+
+    /// Strength of every point's membership to its cluster, in [0, 1]
+    pub fn probabilities(&self) -> &[f64] { &self.probabilities }
+
+    /// GLOSH outlier score of every point, in [0, 1]
+    pub fn outlier_scores(&self) -> &[f64] { &self.outlier_scores }
+
+    pub fn condensed_tree(&self) -> &[CondensedEdge] { &self.condensed_tree }
+
+    /// Stability of every selected cluster, indexed by cluster label
+    pub fn stabilities(&self) -> &[f64] { &self.stabilities }
+
+    pub fn no_clusters(&self) -> usize { self.stabilities.len() }
+
+    pub fn set_min_cluster_size(self, min_cluster_size: usize) -> Self {
+        HDBSCAN { min_cluster_size, .. self }
+    }
+
+    pub fn set_min_samples(self, min_samples: usize) -> Self {
+        HDBSCAN { min_samples: Some(min_samples), .. self }
+    }
+
+    pub fn set_cluster_selection(self, cluster_selection: ClusterSelection) -> Self {
+        HDBSCAN { cluster_selection, .. self }
+    }
+
+    /// Neighbour search used for the core distances only, see `run`
+    pub fn set_neighbor_search(self, neighbor_search: NeighborSearch) -> Self {
+        HDBSCAN { neighbor_search, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use statistics::distance::Euclidean;
+
+    #[test]
+    fn can_run_hdbscan_on_variable_density() {
+        let mut rng = rand::thread_rng();
+
+        let mut points: Vec<Point> = [(0.0, 0.0, 0.5), (10.0, 0.0, 3.0)].iter().flat_map(|&(x, y, spread)| {
+            (0..150).map(|_| Point::new(vec![x + spread * rng.next_f64(), y + spread * rng.next_f64()])).collect::<Vec<Point>>()
+        }).collect();
+
+        points.push(Point::new(vec![40.0, 40.0]));
+
+        let output = HDBSCAN::new().set_min_cluster_size(10).run::<Euclidean>(&points);
+
+        assert_eq!(2, output.no_clusters());
+        assert_eq!(None, output.assignments()[300]);
+        assert!(output.assignments()[..150].iter().filter(|a| **a == output.assignments()[0]).count() > 140);
+        assert!(output.assignments()[150..300].iter().filter(|a| **a == output.assignments()[150]).count() > 140);
+        assert!(output.assignments()[0] != output.assignments()[150]);
+        assert!(output.probabilities().iter().all(|p| *p >= 0.0 && *p <= 1.0));
+        assert!(output.outlier_scores()[300] > 0.9);
+    }
+
+    #[test]
+    fn can_run_hdbscan_on_duplicate_points() {
+        // Each blob is 20 copies of one point with a tail of points leaving at increasing distances
+        let points: Vec<Point> = [0.0, 100.0].iter().flat_map(|&x| {
+            (0..20).map(|_| 0.0).chain([0.1, 0.3, 0.6, 1.0, 1.5].iter().cloned()).map(|offset| Point::new(vec![x + offset, 0.0])).collect::<Vec<Point>>()
+        }).collect();
+
+        let output = HDBSCAN::new().set_min_cluster_size(5).set_min_samples(1).run::<Euclidean>(&points);
+
+        assert_eq!(2, output.no_clusters());
+        assert!(output.assignments()[..25].iter().all(|a| *a == output.assignments()[0]));
+        assert!(output.assignments()[25..].iter().all(|a| *a == output.assignments()[25]));
+        assert!(output.stabilities().iter().all(|s| !s.is_nan()));
+        assert!(output.probabilities().iter().all(|p| *p > 1e-6 && *p <= 1.0));
+        assert!(output.outlier_scores().iter().all(|o| *o >= 0.0 && *o < 1.0));
+    }
+
+    #[test]
+    fn leaf_selection_returns_leaves() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..200).map(|_| Point::new(vec![rng.next_f64(), rng.next_f64()])).collect();
+
+        let output = HDBSCAN::new().set_cluster_selection(Leaf).run::<Euclidean>(&points);
+        let n = points.len();
+
+        let parents: Vec<usize> = output.condensed_tree().iter().filter(|e| e.child >= n).map(|e| e.parent).collect();
+        let leaves = output.condensed_tree().iter().filter(|e| e.child >= n && !parents.contains(&e.child)).count();
+
+        assert_eq!(leaves, output.no_clusters());
+        assert!(output.assignments().iter().all(|a| a.map_or(true, |c| c < output.no_clusters())));
+    }
+}
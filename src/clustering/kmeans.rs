@@ -10,14 +10,14 @@ use point::Point;
 use clustering::kmeans::KMeansInitialization::*;
 use statistics::distance::{Distance, SquaredEuclidean};
 use statistics::statistics::Statistics;
+use neighbors::{NeighborIndex, assign_nearest};
 use rayon::prelude::*;
-use std::collections::HashMap;
-use itertools::Itertools;
 
 #[derive(Copy, Clone, Debug)]
 pub enum KMeansInitialization {
     Random,
     KMeansPlusPlus,
+    /// Starts from the centroids given to `set_precomputed`, as many as there are whatever `no_clusters` is
     Precomputed
 }
 
@@ -52,27 +52,61 @@ impl KMeans {
         KMeans::default()
     }
 
+    /// Lloyd iterations with unit weights. A centroid left without points keeps its position,
+    /// so there are always `no_clusters` centroids for non-empty input.
     pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
-        let mut centroids = self.initial_centroids(points, no_clusters);
+        let weights = vec![1.0; points.len()];
+        self.run_weighted(points, &weights, no_clusters)
+    }
+
+    /// Lloyd iterations where every point pulls its centroid in proportion to its weight
+    pub fn run_weighted(self, points: &[Point], weights: &[f64], no_clusters: usize) -> Self {
+        if weights.len() != points.len() {
+            panic!("Expected {} weights, one per point, found {}", points.len(), weights.len());
+        }
+
+        if weights.iter().any(|w| *w < 0.0) {
+            panic!("Expected non-negative weights");
+        }
+
+        if points.is_empty() || no_clusters == 0 {
+            return KMeans { assignments: vec![], centroids: vec![], iterations: 0, converged: true, .. self }
+        }
+
+        let mut centroids = self.weighted_initial_centroids(points, weights, no_clusters);
+        let dimension = points[0].coordinates().len();
 
         let mut i = 0;
         let stop_condition = self.tolerance * self.tolerance;
 
         while i < self.max_iterations {
-            let updated_centroids: Vec<Vec<f64>> =
-                points.par_iter().fold(|| HashMap::with_capacity(no_clusters), |mut new_centroids, point| {
-                    let (index_c, _) = Self::closest_centroid(point.coordinates(), centroids.as_slice());
-                    (*new_centroids.entry(index_c).or_insert(vec![])).push(point.coordinates());
-                    new_centroids
-                }).reduce(|| HashMap::with_capacity(no_clusters), |mut new_centroids, partial| {
-                    for (k, v) in partial.into_iter() {
-                        (*new_centroids.entry(k).or_insert(vec![])).extend(v);
+            let (sums, totals) = points.par_iter().zip(weights.par_iter()).fold(|| (vec![vec![0.0; dimension]; centroids.len()], vec![0.0; centroids.len()]), |(mut sums, mut totals), (point, weight)| {
+                let (index_c, _) = Self::closest_centroid(point.coordinates(), centroids.as_slice());
+                for (s, x) in sums[index_c].iter_mut().zip(point.coordinates().iter()) {
+                    *s += weight * x;
+                }
+
+                totals[index_c] += *weight;
+                (sums, totals)
+            }).reduce(|| (vec![vec![0.0; dimension]; centroids.len()], vec![0.0; centroids.len()]), |(mut sums, mut totals), (partial_sums, partial_totals)| {
+                for (index_c, partial) in partial_sums.into_iter().enumerate() {
+                    for (s, x) in sums[index_c].iter_mut().zip(partial.into_iter()) {
+                        *s += x;
                     }
 
-                    new_centroids
-                }).into_iter().sorted_by(|a, b| a.0.cmp(&b.0)).into_iter().map(|(_, ref v)| {
-                    Statistics::mean(&v)
-                }).collect();
+                    totals[index_c] += partial_totals[index_c];
+                }
+
+                (sums, totals)
+            });
+
+            // Centroids without any weight stay where they are
+            let updated_centroids: Vec<Vec<f64>> = sums.into_iter().zip(totals.into_iter()).zip(centroids.iter()).map(|((sum, total), centroid)| {
+                match total > 0.0 {
+                    true => sum.into_iter().map(|s| s / total).collect(),
+                    false => centroid.clone()
+                }
+            }).collect();
 
             let change = Statistics::max_change(centroids.as_slice(), updated_centroids.as_slice());
             centroids = updated_centroids;
@@ -93,6 +127,11 @@ impl KMeans {
     }
 
     pub fn initial_centroids(&self, points: &[Point], no_clusters: usize) -> Vec<Vec<f64>> {
+        self.weighted_initial_centroids(points, &vec![1.0; points.len()], no_clusters)
+    }
+
+    /// KMeans++ seeding draws every centroid in proportion to the weight of the point times its squared distance
+    fn weighted_initial_centroids(&self, points: &[Point], weights: &[f64], no_clusters: usize) -> Vec<Vec<f64>> {
         match self.init_method {
             Random => {
                 let mut rng = rand::thread_rng();
@@ -104,37 +143,57 @@ impl KMeans {
             },
             KMeansPlusPlus => {
                 let mut rng = rand::thread_rng();
-                let between = Range::new(0, points.len());
 
-                let mut distances: Vec<f64> = vec![0.0; points.len()];
-                let mut centroids: Vec<Vec<f64>> = vec![points[between.ind_sample(&mut rng)].coordinates().to_vec()];
+                let mut centroids: Vec<Vec<f64>> = vec![points[Self::sample_index(weights, &mut rng)].coordinates().to_vec()];
 
                 for _ in 1..no_clusters {
-                    let mut sum = points.iter().enumerate().fold(0.0, |sum, (index_p, p)| {
-                        let (_, distance_c) = Self::closest_centroid(p.coordinates(), centroids.as_slice());
-                        distances[index_p] = distance_c;
-                        sum + distance_c
-                    });
-
-                    sum *= rng.next_f64();
-                    for (index_p, d) in distances.iter().enumerate() {
-                        sum -= *d;
-
-                        if sum <= 0f64 {
-                            centroids.push(points[index_p].coordinates().to_vec());
-                            break;
-                        }
-                    }
+                    let scores: Vec<f64> = points.iter().zip(weights.iter()).map(|(p, w)| {
+                        w * Self::closest_centroid(p.coordinates(), centroids.as_slice()).1
+                    }).collect();
+
+                    centroids.push(points[Self::sample_index(&scores, &mut rng)].coordinates().to_vec());
                 }
 
                 centroids
             },
             Precomputed => {
-                vec![]//self.precomputed.expect("Expected a vec of clusters, on the form Vec<f64>").clone()
+                let centroids = self.precomputed.clone().expect("Expected a vec of clusters, on the form Vec<f64>");
+                if centroids.is_empty() {
+                    panic!("Expected at least one precomputed centroid")
+                }
+
+                if let Some(point) = points.first() {
+                    let dimension = point.coordinates().len();
+                    if let Some(c) = centroids.iter().find(|c| c.len() != dimension) {
+                        panic!("Expected precomputed centroids of dimension {}, found {}", dimension, c.len())
+                    }
+                }
+
+                centroids
             }
         }
     }
 
+    /// Draws an index with probability proportional to its score
+    fn sample_index<R: Rng>(scores: &[f64], rng: &mut R) -> usize {
+        let mut sum = scores.iter().sum::<f64>() * rng.next_f64();
+
+        scores.iter().position(|score| {
+            sum -= *score;
+            sum <= 0f64
+        }).unwrap_or(scores.len() - 1)
+    }
+
+    pub fn predict(&self, points: &[Point]) -> Vec<usize> {
+        let centroids: Vec<Vec<f64>> = self.centroids.iter().map(|c| c.coordinates().to_vec()).collect();
+        points.par_iter().map(|p| Self::closest_centroid(p.coordinates(), centroids.as_slice()).0).collect()
+    }
+
+    /// Assigns points to their closest centroid using an index built over `centroids()`, which must not be empty
+    pub fn predict_with_index<I: NeighborIndex + Sync>(&self, points: &[Point], index: &I) -> Vec<usize> {
+        assign_nearest(points, index)
+    }
+
     #[inline]
     fn closest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
         match centroids.iter().enumerate().map(|(index_c, c)| {
@@ -182,6 +241,7 @@ mod tests {
     use rand;
     use rand::Rng;
     use datasets::*;
+    use neighbors::hnsw::HNSW;
 
     #[test]
     fn can_run_kmeans() {
@@ -217,4 +277,55 @@ mod tests {
         assert_eq!(3, output.centroids().len());
         assert!(iris::load().target().iter().zip(output.assignments().iter()).all(|(a, b)| a == b));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn can_predict_with_index() {
+        let output = KMeans::new().run(iris::load().data(), 3);
+        let index: HNSW<SquaredEuclidean> = HNSW::new().build(output.centroids());
+
+        assert_eq!(output.assignments(), output.predict(iris::load().data()).as_slice());
+        assert_eq!(output.predict(iris::load().data()), output.predict_with_index(iris::load().data(), &index));
+    }
+
+    #[test]
+    fn weights_pull_centroids() {
+        let points = vec![Point::new(vec![0.0]), Point::new(vec![1.0]), Point::new(vec![10.0])];
+        let output = KMeans::new().set_init_method(KMeansPlusPlus).run_weighted(&points, &[1.0, 3.0, 0.0], 1);
+
+        assert_eq!(&[0.75], output.centroids()[0].coordinates());
+    }
+
+    #[test]
+    fn keeps_centroids_without_points() {
+        let points = vec![Point::new(vec![0.0]), Point::new(vec![1.0]), Point::new(vec![2.0])];
+        let output = KMeans::new().set_init_method(Precomputed)
+                                  .set_precomputed(&Some(vec![vec![0.0], vec![100.0]]))
+                                  .run(&points, 2);
+
+        assert_eq!(2, output.centroids().len());
+        assert_eq!(&[1.0], output.centroids()[0].coordinates());
+        assert_eq!(&[100.0], output.centroids()[1].coordinates());
+        assert_eq!(&[0, 0, 0], output.assignments());
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected precomputed centroids of dimension 1, found 2")]
+    fn precomputed_centroids_match_dimension() {
+        let points = vec![Point::new(vec![0.0]), Point::new(vec![1.0]), Point::new(vec![2.0])];
+        KMeans::new().set_init_method(Precomputed).set_precomputed(&Some(vec![vec![0.0], vec![1.0, 1.0]])).run(&points, 2);
+    }
+
+    #[test]
+    fn can_run_without_points_or_clusters() {
+        assert!(KMeans::new().run(&[], 3).centroids().is_empty());
+        assert!(KMeans::new().set_init_method(KMeansPlusPlus).run(iris::load().data(), 0).assignments().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 3 weights, one per point, found 2")]
+    fn rejects_weights_of_other_length() {
+        let points = vec![Point::new(vec![0.0]), Point::new(vec![1.0]), Point::new(vec![10.0])];
+
+        KMeans::new().run_weighted(&points, &[1.0, 3.0], 1);
+    }
+}
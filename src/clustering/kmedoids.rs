@@ -1,30 +1,49 @@
+// https://arxiv.org/abs/2008.05171
+
 use rand;
 use rand::Rng;
 use rand::distributions::{IndependentSample, Range};
 
 use std::cmp::Ordering;
-use std::usize;
+use std::f64;
+use std::marker::PhantomData;
 use point::Point;
-use std::collections::HashMap;
-use statistics::distance::{Distance, Manhattan};
-use statistics::statistics::Statistics;
+use statistics::distance::Distance;
 use clustering::kmedoids::KMedoidsInitialization::*;
+use clustering::kmedoids::KMedoidsAlgorithm::*;
 use rayon::prelude::*;
 
 #[derive(Copy, Clone, Debug)]
 pub enum KMedoidsInitialization {
     Random,
     KMeansPlusPlus,
+    /// The greedy BUILD phase of PAM
+    Build,
     Precomputed
 }
 
+#[derive(Copy, Clone, Debug)]
+pub enum KMedoidsAlgorithm {
+    /// Voronoi iteration, alternating between assigning the points and moving every medoid to the most central member of its cluster
+    Alternate,
+    /// The SWAP phase of PAM, trying every pair of medoid and non-medoid and applying the best swap
+    PAM,
+    /// PAM with the change of every medoid computed in one pass per non-medoid, O(k) faster with the same result
+    FastPAM1,
+    /// FastPAM1 applying any improving swap right away instead of searching for the best one
+    FasterPAM
+}
+
 pub struct KMedoids {
     assignments: Vec<usize>,
+    medoids: Vec<usize>,
     centroids: Vec<Point>,
+    deviation: f64,
     iterations: usize,
     converged: bool,
+    algorithm: KMedoidsAlgorithm,
     init_method: KMedoidsInitialization,
-    precomputed: Option<Vec<Vec<f64>>>,
+    precomputed: Option<Vec<usize>>,
     max_iterations: usize,
     tolerance: f64
 }
@@ -33,148 +52,506 @@ impl Default for KMedoids {
     fn default() -> KMedoids {
         KMedoids {
             assignments: vec![],
+            medoids: vec![],
             centroids: vec![],
+            deviation: 0.0,
             iterations: 0,
             converged: false,
-            init_method: Random,
+            algorithm: FasterPAM,
+            init_method: Build,
             precomputed: None,
-            max_iterations: 15,
+            max_iterations: 100,
             tolerance: 0.00001
         }
     }
 }
 
-impl KMedoids {
-    pub fn new() -> Self {
-        KMedoids::default()
+/// Distances between the clustered points, given by their indices
+trait Dissimilarities: Sync {
+    fn no_points(&self) -> usize;
+
+    fn distance(&self, a: usize, b: usize) -> f64;
+}
+
+impl Dissimilarities for [Vec<f64>] {
+    fn no_points(&self) -> usize { self.len() }
+
+    #[inline]
+    fn distance(&self, a: usize, b: usize) -> f64 { self[a][b] }
+}
+
+/// Distances computed when needed instead of kept in a matrix
+struct PointDistances<'a, D: Distance> {
+    points: &'a [Point],
+    metric: PhantomData<D>
+}
+
+impl <'a, D: Distance> Dissimilarities for PointDistances<'a, D> {
+    fn no_points(&self) -> usize { self.points.len() }
+
+    #[inline]
+    fn distance(&self, a: usize, b: usize) -> f64 {
+        D::distance(self.points[a].coordinates(), self.points[b].coordinates())
     }
+}
 
-    pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
-        let mut medoids = self.initial_medoids(points, no_clusters);
-        let mut cached_medoids: Vec<&[f64]> = medoids.iter().map(|(index_m, _)| points[*index_m].coordinates()).collect();
+/// Nearest and second nearest medoid of every point, the medoids given by their position in the medoid list
+struct Nearest {
+    nearest: Vec<usize>,
+    first: Vec<f64>,
+    second: Vec<f64>,
+    removal_loss: Vec<f64>
+}
 
-        let mut i = 0;
-        let stop_condition = self.tolerance * self.tolerance;
+impl Nearest {
+    fn new<M: Dissimilarities + ?Sized>(distances: &M, medoids: &[usize]) -> Nearest {
+        let closest: Vec<(usize, f64, f64)> = (0..distances.no_points()).into_par_iter().map(|index_p| {
+            medoids.iter().enumerate().fold((0, f64::INFINITY, f64::INFINITY), |(nearest, first, second), (index_m, m)| {
+                let distance = distances.distance(*m, index_p);
 
-        while i < self.max_iterations {
-            let updated_medoids: HashMap<usize, Vec<usize>> =
-                points.par_iter().enumerate().fold(|| HashMap::with_capacity(no_clusters), |mut new_medoids, (index_p, point)| {
-                    let (index_c, _) = Self::closest_medoid(point.coordinates(), cached_medoids.as_slice());
-                    (*new_medoids.entry(index_c).or_insert(vec![])).push(index_p);
-                    new_medoids
-                }).reduce(|| HashMap::with_capacity(no_clusters), |mut new_medoids, partial| {
-                    for (k, v) in partial.into_iter() {
-                        (*new_medoids.entry(k).or_insert(vec![])).extend(v);
-                    }
+                if distance < first {
+                    (index_m, distance, first)
+                } else if distance < second {
+                    (nearest, first, distance)
+                } else {
+                    (nearest, first, second)
+                }
+            })
+        }).collect();
+
+        let nearest: Vec<usize> = closest.iter().map(|c| c.0).collect();
+        let first: Vec<f64> = closest.iter().map(|c| c.1).collect();
+        let second: Vec<f64> = closest.iter().map(|c| c.2).collect();
+
+        // Increase of the deviation if the medoid was removed, every point falling back to its second nearest medoid
+        let mut removal_loss = vec![0.0; medoids.len()];
+        for index_p in 0..distances.no_points() {
+            removal_loss[nearest[index_p]] += second[index_p] - first[index_p];
+        }
+
+        Nearest { nearest, first, second, removal_loss }
+    }
 
-                    new_medoids
-                }).into_iter().map(|(index_c, cluster_points)| {
-                    let current_cost = cluster_points.iter().map(|index_p| {
-                        Manhattan::distance(points[*index_p].coordinates(), points[index_c].coordinates())
-                    }).sum();
-
-                    let (medoid, _) = match cluster_points.iter().map(|candidate_medoid| {
-                        let old = Manhattan::distance(points[index_c].coordinates(), points[*candidate_medoid].coordinates());
-                        let new = cluster_points
-                                    .iter()
-                                    .filter(|index_p| *index_p != candidate_medoid)
-                                    .map(|index_p| {
-                                        Manhattan::distance(points[*index_p].coordinates(), points[*candidate_medoid].coordinates())
-                                    })
-                                    .sum::<f64>();
-
-                        (candidate_medoid, old + new)
-                    }).min_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal)) {
-                        Some((candidate, distance)) if distance < current_cost => (*candidate, distance),
-                        Some(_) => (index_c, current_cost),
-                        None => panic!()
-                    };
-
-                    (medoid, vec![])
+    fn deviation(&self) -> f64 {
+        self.first.iter().sum()
+    }
+
+    // Change of the deviation when the medoid at `index_m` is replaced by the point `h`
+    fn swap_delta<M: Dissimilarities + ?Sized>(&self, distances: &M, index_m: usize, h: usize) -> f64 {
+        (0..distances.no_points()).map(|index_p| {
+            let distance = distances.distance(h, index_p);
+
+            match self.nearest[index_p] == index_m {
+                true => distance.min(self.second[index_p]) - self.first[index_p],
+                false => (distance - self.first[index_p]).min(0.0)
+            }
+        }).sum()
+    }
+
+    // Change of the deviation for every medoid replaced by the point `h`, in a single pass over the points
+    fn swap_deltas<M: Dissimilarities + ?Sized>(&self, distances: &M, h: usize) -> Vec<f64> {
+        // Without a second nearest medoid the removal loss is infinite
+        if self.removal_loss.len() == 1 {
+            return vec![self.swap_delta(distances, 0, h)]
+        }
+
+        let mut deltas = self.removal_loss.clone();
+        let mut shared = 0.0;
+
+        for index_p in 0..distances.no_points() {
+            let distance = distances.distance(h, index_p);
+
+            if distance < self.first[index_p] {
+                shared += distance - self.first[index_p];
+                deltas[self.nearest[index_p]] += self.first[index_p] - self.second[index_p];
+            } else if distance < self.second[index_p] {
+                deltas[self.nearest[index_p]] += distance - self.second[index_p];
+            }
+        }
+
+        deltas.iter().map(|delta| delta + shared).collect()
+    }
+}
+
+impl KMedoids {
+    pub fn new() -> Self {
+        KMedoids::default()
+    }
+
+    /// Clusters the points under the metric `D`. The swap algorithms first compute the n × n distance matrix,
+    /// O(n²) memory (80 GB for 100000 points), while `Alternate` computes the distances it needs in O(n) memory.
+    /// The `Build` initialization still takes O(n²) time, `KMeansPlusPlus` suits large data sets better.
+    pub fn run<D: Distance>(self, points: &[Point], no_clusters: usize) -> Self {
+        let output = match self.algorithm {
+            Alternate => self.cluster(&PointDistances::<D> { points, metric: PhantomData }, no_clusters),
+            _ => {
+                let distances: Vec<Vec<f64>> = points.par_iter().map(|p| {
+                    points.iter().map(|q| D::distance(p.coordinates(), q.coordinates())).collect()
                 }).collect();
 
-            let updated_centroids: Vec<&[f64]> = updated_medoids.iter().map(|(index_m, _)| points[*index_m].coordinates()).collect();
-            let change = Statistics::max_change_slice(cached_medoids.as_slice(), updated_centroids.as_slice());
-            medoids = updated_medoids;
-            cached_medoids = updated_centroids;
-            if change <= stop_condition {
-                break;
+                self.run_precomputed(&distances, no_clusters)
             }
+        };
 
-            i += 1;
+        KMedoids {
+            centroids: output.medoids.iter().map(|index_m| points[*index_m].clone()).collect(),
+            .. output
         }
+    }
+
+    /// Clusters from a symmetric distance matrix
+    pub fn run_precomputed(self, distances: &[Vec<f64>], no_clusters: usize) -> Self {
+        self.cluster(distances, no_clusters)
+    }
+
+    fn cluster<M: Dissimilarities + ?Sized>(self, distances: &M, no_clusters: usize) -> Self {
+        let mut medoids = self.initial_medoids(distances, no_clusters);
+        if medoids.is_empty() {
+            return KMedoids { assignments: vec![], medoids, centroids: vec![], deviation: 0.0, iterations: 0, converged: true, .. self }
+        }
+
+        let (iterations, converged) = match self.algorithm {
+            Alternate => self.alternate(distances, &mut medoids),
+            PAM => self.swap(distances, &mut medoids, false),
+            FastPAM1 => self.swap(distances, &mut medoids, true),
+            FasterPAM => self.eager_swap(distances, &mut medoids)
+        };
+
+        let nearest = Nearest::new(distances, &medoids);
 
         KMedoids {
-            assignments: points.iter().map(|p| Self::closest_medoid(p.coordinates(), cached_medoids.as_slice()).0).collect(),
-            centroids: medoids.into_iter().map(|(index_m, _)| points[index_m].clone()).collect(),
-            iterations: i,
-            converged: i < self.max_iterations,
+            assignments: nearest.nearest.clone(),
+            medoids,
+            centroids: vec![],
+            deviation: nearest.deviation(),
+            iterations,
+            converged,
             .. self
         }
     }
 
-    fn initial_medoids(&self, points: &[Point], no_clusters: usize) -> HashMap<usize, Vec<usize>> {
+    fn alternate<M: Dissimilarities + ?Sized>(&self, distances: &M, medoids: &mut Vec<usize>) -> (usize, bool) {
+        for i in 0..self.max_iterations {
+            let nearest = Nearest::new(distances, medoids);
+
+            // A medoid whose cluster emptied out stays in place
+            let updated: Vec<usize> = (0..medoids.len()).into_par_iter().map(|index_m| {
+                let members: Vec<usize> = (0..distances.no_points()).filter(|index_p| nearest.nearest[*index_p] == index_m).collect();
+
+                members.iter().map(|candidate| {
+                    (*candidate, members.iter().map(|index_p| distances.distance(*candidate, *index_p)).sum::<f64>())
+                }).min_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal)).map(|(candidate, _)| candidate).unwrap_or(medoids[index_m])
+            }).collect();
+
+            if updated == *medoids {
+                return (i, true)
+            }
+
+            *medoids = updated;
+        }
+
+        (self.max_iterations, false)
+    }
+
+    fn swap<M: Dissimilarities + ?Sized>(&self, distances: &M, medoids: &mut Vec<usize>, fast: bool) -> (usize, bool) {
+        for i in 0..self.max_iterations {
+            let nearest = Nearest::new(distances, medoids);
+
+            let best = (0..distances.no_points()).into_par_iter().filter(|h| !medoids.contains(h)).map(|h| {
+                let deltas = match fast {
+                    true => nearest.swap_deltas(distances, h),
+                    false => (0..medoids.len()).map(|index_m| nearest.swap_delta(distances, index_m, h)).collect()
+                };
+
+                let (index_m, delta) = Self::smallest(&deltas);
+                (index_m, h, delta)
+            }).min_by(|&(_, _, a), &(_, _, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+
+            match best {
+                Some((index_m, h, delta)) if delta < -self.tolerance => medoids[index_m] = h,
+                _ => return (i, true)
+            }
+        }
+
+        (self.max_iterations, false)
+    }
+
+    // Every pass visits the non-medoids in order, stopping once a whole round went without a swap
+    fn eager_swap<M: Dissimilarities + ?Sized>(&self, distances: &M, medoids: &mut Vec<usize>) -> (usize, bool) {
+        let mut nearest = Nearest::new(distances, medoids);
+        let mut unchanged = 0;
+
+        for i in 0..self.max_iterations {
+            for h in 0..distances.no_points() {
+                if unchanged >= distances.no_points() {
+                    return (i, true)
+                }
+
+                unchanged += 1;
+                if medoids.contains(&h) {
+                    continue;
+                }
+
+                let (index_m, delta) = Self::smallest(&nearest.swap_deltas(distances, h));
+                if delta < -self.tolerance {
+                    medoids[index_m] = h;
+                    nearest = Nearest::new(distances, medoids);
+                    unchanged = 0;
+                }
+            }
+        }
+
+        (self.max_iterations, unchanged >= distances.no_points())
+    }
+
+    fn initial_medoids<M: Dissimilarities + ?Sized>(&self, distances: &M, no_clusters: usize) -> Vec<usize> {
+        let n = distances.no_points();
+        let row = |index_p: usize| -> Vec<f64> { (0..n).map(|index_q| distances.distance(index_p, index_q)).collect() };
+        let no_clusters = no_clusters.min(n);
+        if no_clusters == 0 {
+            return vec![]
+        }
+
         match self.init_method {
             Random => {
-                let mut rng = rand::thread_rng();
-                let between = Range::new(0, points.len());
-
-                (0..no_clusters).map(|_| {
-                    (between.ind_sample(&mut rng), vec![])
-                }).collect()
+                let mut medoids: Vec<usize> = (0..n).collect();
+                rand::thread_rng().shuffle(&mut medoids);
+                medoids.truncate(no_clusters);
+                medoids
             },
             KMeansPlusPlus => {
                 let mut rng = rand::thread_rng();
-                let between = Range::new(0, points.len());
-
-                let mut distances: Vec<f64> = vec![0.0; points.len()];
-                let mut centroids: Vec<&[f64]> = vec![points[between.ind_sample(&mut rng)].coordinates()];
-                let mut result: HashMap<usize, Vec<usize>> = HashMap::with_capacity(no_clusters);
-
-                for _ in 1..no_clusters {
-                    let mut sum = points.iter().enumerate().fold(0.0, |sum, (index_p, p)| {
-                        let (_, distance_c) = Self::closest_medoid(p.coordinates(), centroids.as_slice());
-                        distances[index_p] = distance_c;
-                        sum + distance_c
-                    });
-
-                    sum *= rng.next_f64();
-                    for (index_p, d) in distances.iter().enumerate() {
-                        sum -= *d;
-
-                        if sum <= 0f64 {
-                            centroids.push(points[index_p].coordinates());
-                            result.insert(index_p, vec![]);
+                let mut medoids = vec![Range::new(0, n).ind_sample(&mut rng)];
+                let mut closest = row(medoids[0]);
+
+                while medoids.len() < no_clusters {
+                    // Sample in proportion to the squared distance to the closest medoid
+                    let mut sum = closest.iter().map(|d| d * d).sum::<f64>() * rng.next_f64();
+                    let mut next = None;
+
+                    for (index_p, d) in closest.iter().enumerate() {
+                        sum -= d * d;
+
+                        if sum <= 0f64 && *d > 0f64 {
+                            next = Some(index_p);
                             break;
                         }
                     }
+
+                    // Only duplicates of the medoids are left
+                    let next = next.unwrap_or_else(|| (0..n).find(|index_p| !medoids.contains(index_p)).unwrap());
+                    for (index_p, c) in closest.iter_mut().enumerate() {
+                        *c = c.min(distances.distance(next, index_p));
+                    }
+
+                    medoids.push(next);
+                }
+
+                medoids
+            },
+            Build => {
+                let first = (0..n).into_par_iter().map(|index_p| (index_p, (0..n).map(|index_q| distances.distance(index_p, index_q)).sum::<f64>()))
+                                  .min_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+
+                let mut medoids: Vec<usize> = first.into_iter().map(|(index_p, _)| index_p).collect();
+                let mut closest = match medoids.first() {
+                    Some(m) => row(*m),
+                    None => vec![]
+                };
+
+                // Greedily add the point that decreases the deviation the most
+                while medoids.len() < no_clusters {
+                    let next = (0..n).into_par_iter().filter(|index_p| !medoids.contains(index_p)).map(|candidate| {
+                        (candidate, closest.iter().enumerate().map(|(index_p, c)| (c - distances.distance(candidate, index_p)).max(0.0)).sum::<f64>())
+                    }).max_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal)).unwrap().0;
+
+                    for (index_p, c) in closest.iter_mut().enumerate() {
+                        *c = c.min(distances.distance(next, index_p));
+                    }
+
+                    medoids.push(next);
                 }
 
-                result
+                medoids
             },
             Precomputed => {
-                HashMap::new()
+                let medoids = self.precomputed.clone().expect("Expected a vec of medoids, on the form Vec<usize>");
+                if medoids.len() != no_clusters {
+                    panic!("Expected {} precomputed medoids, found {}", no_clusters, medoids.len())
+                }
+
+                for (index_m, m) in medoids.iter().enumerate() {
+                    if *m >= n {
+                        panic!("Expected precomputed medoids below {}, found {}", n, m)
+                    }
+
+                    if medoids[..index_m].contains(m) {
+                        panic!("Expected distinct precomputed medoids, found {} twice", m)
+                    }
+                }
+
+                medoids
             }
         }
     }
 
     #[inline]
-    fn closest_medoid(point: &[f64], centroids: &[&[f64]]) -> (usize, f64) {
-        match centroids.iter().enumerate().map(|(index_c, c)| {
-            (index_c, Manhattan::distance(point, c))
-        }).min_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!()
-        }
+    fn smallest(values: &[f64]) -> (usize, f64) {
+        values.iter().cloned().enumerate().min_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal)).unwrap_or((0, f64::INFINITY))
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Indices of the medoids among the clustered points
+    pub fn medoids(&self) -> &[usize] { &self.medoids }
+
+    /// The medoids themselves, only filled by `run`
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// Sum of the distances of every point to its medoid
+    pub fn deviation(&self) -> f64 { self.deviation }
+
+    /// Number of swaps for PAM and FastPAM1, of passes over the data otherwise
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn set_algorithm(self, algorithm: KMedoidsAlgorithm) -> Self {
+        KMedoids { algorithm, .. self }
+    }
+
+    pub fn set_init_method(self, init_method: KMedoidsInitialization) -> Self {
+        KMedoids { init_method, .. self }
+    }
+
+    /// Indices of the initial medoids, used by the `Precomputed` initialization
+    pub fn set_precomputed(self, precomputed: &Option<Vec<usize>>) -> Self {
+        KMedoids { precomputed: precomputed.clone(), .. self }
+    }
+
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        KMedoids { max_iterations, .. self }
+    }
+
+    /// Smallest decrease of the deviation for a swap to be applied
+    pub fn set_tolerance(self, tolerance: f64) -> Self {
+        KMedoids { tolerance, .. self }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand;
-    use rand::Rng;
-    use time;
-}
\ No newline at end of file
+    use datasets::blobs::make_blobs;
+    use datasets::*;
+    use statistics::distance::{Euclidean, Manhattan};
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_kmedoids() {
+        let points = blobs();
+
+        for algorithm in [Alternate, PAM, FastPAM1, FasterPAM].iter() {
+            let output = KMedoids::new().set_algorithm(*algorithm).set_init_method(KMeansPlusPlus).run::<Manhattan>(&points, 3);
+
+            assert!(output.converged());
+            assert_eq!(3, output.centroids().len());
+            for blob in 0..3 {
+                assert!(output.assignments()[blob * 100..(blob + 1) * 100].iter().all(|a| *a == output.assignments()[blob * 100]));
+            }
+        }
+    }
+
+    #[test]
+    fn fast_pam_matches_pam_on_iris() {
+        let pam = KMedoids::new().set_algorithm(PAM).run::<Euclidean>(iris::load().data(), 3);
+        let fast_pam = KMedoids::new().set_algorithm(FastPAM1).run::<Euclidean>(iris::load().data(), 3);
+        let faster_pam = KMedoids::new().set_algorithm(FasterPAM).run::<Euclidean>(iris::load().data(), 3);
+
+        assert_eq!(pam.medoids(), fast_pam.medoids());
+        assert!((pam.deviation() - fast_pam.deviation()).abs() < 1e-9);
+        assert!(faster_pam.deviation() <= pam.deviation() * 1.01);
+
+        // No single swap improves the PAM result
+        let distances: Vec<Vec<f64>> = iris::load().data().iter().map(|p| {
+            iris::load().data().iter().map(|q| Euclidean::distance(p.coordinates(), q.coordinates())).collect()
+        }).collect();
+        let nearest = Nearest::new(distances.as_slice(), pam.medoids());
+
+        for h in (0..distances.len()).filter(|h| !pam.medoids().contains(h)) {
+            let deltas = nearest.swap_deltas(distances.as_slice(), h);
+            for index_m in 0..3 {
+                assert!((deltas[index_m] - nearest.swap_delta(distances.as_slice(), index_m, h)).abs() < 1e-9);
+                assert!(deltas[index_m] > -1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_every_medoid() {
+        let points = blobs();
+
+        for _ in 0..10 {
+            let output = KMedoids::new().set_algorithm(Alternate).set_init_method(Random).run::<Manhattan>(&points, 10);
+            let mut medoids = output.medoids().to_vec();
+            medoids.sort();
+            medoids.dedup();
+
+            assert_eq!(10, medoids.len());
+            assert!(output.assignments().iter().all(|a| *a < 10));
+        }
+    }
+
+    #[test]
+    fn can_run_without_clusters() {
+        let points = blobs();
+
+        for init_method in [Random, KMeansPlusPlus, Build].iter() {
+            for algorithm in [Alternate, PAM, FastPAM1, FasterPAM].iter() {
+                let output = KMedoids::new().set_init_method(*init_method).set_algorithm(*algorithm).run::<Manhattan>(&points, 0);
+                assert!(output.medoids().is_empty());
+
+                let output = KMedoids::new().set_init_method(*init_method).set_algorithm(*algorithm).run::<Manhattan>(&[], 3);
+                assert!(output.medoids().is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn alternate_matches_on_points_and_matrix() {
+        let points = blobs();
+        let distances: Vec<Vec<f64>> = points.iter().map(|p| {
+            points.iter().map(|q| Manhattan::distance(p.coordinates(), q.coordinates())).collect()
+        }).collect();
+
+        let on_points = KMedoids::new().set_algorithm(Alternate).run::<Manhattan>(&points, 3);
+        let on_matrix = KMedoids::new().set_algorithm(Alternate).run_precomputed(&distances, 3);
+
+        assert_eq!(on_matrix.medoids(), on_points.medoids());
+        assert_eq!(on_matrix.assignments(), on_points.assignments());
+        assert!((on_matrix.deviation() - on_points.deviation()).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 3 precomputed medoids, found 2")]
+    fn rejects_precomputed_medoids_of_other_count() {
+        KMedoids::new().set_init_method(Precomputed).set_precomputed(&Some(vec![0, 100])).run::<Manhattan>(&blobs(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected precomputed medoids below 300, found 300")]
+    fn rejects_precomputed_medoids_out_of_range() {
+        KMedoids::new().set_init_method(Precomputed).set_precomputed(&Some(vec![0, 100, 300])).run::<Manhattan>(&blobs(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected distinct precomputed medoids, found 100 twice")]
+    fn rejects_repeated_precomputed_medoids() {
+        KMedoids::new().set_init_method(Precomputed).set_precomputed(&Some(vec![0, 100, 100])).run::<Manhattan>(&blobs(), 3);
+    }
+}
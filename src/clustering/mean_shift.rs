@@ -0,0 +1,282 @@
+// https://courses.csail.mit.edu/6.869/handouts/PAMIMeanshift.pdf
+
+use std::cmp::Ordering;
+use std::f64;
+use std::collections::HashMap;
+use point::Point;
+use clustering::mean_shift::Kernel::*;
+use statistics::distance::{Distance, Euclidean, SquaredEuclidean};
+use neighbors::{NeighborIndex, NeighborSearch};
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Kernel {
+    /// Every point within the bandwidth weighs the same
+    Flat,
+    /// Points are weighted by a gaussian with the bandwidth as standard deviation, truncated at three bandwidths
+    Gaussian
+}
+
+pub struct MeanShift {
+    assignments: Vec<usize>,
+    centroids: Vec<Point>,
+    bandwidth: Option<f64>,
+    quantile: f64,
+    kernel: Kernel,
+    bin_seeding: bool,
+    min_bin_frequency: usize,
+    max_iterations: usize,
+    tolerance: f64,
+    neighbor_search: NeighborSearch
+}
+
+impl Default for MeanShift {
+    fn default() -> MeanShift {
+        MeanShift {
+            assignments: vec![],
+            centroids: vec![],
+            bandwidth: None,
+            quantile: 0.3,
+            kernel: Flat,
+            bin_seeding: false,
+            min_bin_frequency: 1,
+            max_iterations: 300,
+            tolerance: 0.001,
+            neighbor_search: NeighborSearch::BruteForce
+        }
+    }
+}
+
+impl MeanShift {
+    pub fn new() -> Self {
+        MeanShift::default()
+    }
+
+    pub fn run(self, points: &[Point]) -> Self {
+        let bandwidth = match self.bandwidth {
+            Some(bandwidth) => bandwidth,
+            None => Self::estimate_bandwidth(points, self.quantile)
+        };
+
+        let reach = match self.kernel {
+            Flat => bandwidth,
+            Gaussian => 3.0 * bandwidth
+        };
+
+        let index = self.neighbor_search.build::<Euclidean>(points, reach);
+
+        let mut modes = self.modes(points, &*index, self.seeds(points, bandwidth), bandwidth, reach);
+
+        // Bin centers may lie farther than the bandwidth from every point, so fall back to the points themselves
+        if modes.is_empty() {
+            modes = self.modes(points, &*index, points.iter().map(|p| p.coordinates().to_vec()).collect(), bandwidth, reach);
+        }
+
+        let centroids = Self::merge_modes(modes, bandwidth);
+
+        MeanShift {
+            assignments: points.par_iter().map(|p| Self::closest_centroid(p.coordinates(), centroids.as_slice()).0).collect(),
+            centroids: centroids.into_iter().map(|c| Point::new(c)).collect(),
+            bandwidth: Some(bandwidth),
+            .. self
+        }
+    }
+
+    // Shifts every seed to its mode with the number of points within the bandwidth, dropping seeds with no point within reach.
+    // A shift leaving every point out of reach stops at the previous mean.
+    fn modes(&self, points: &[Point], index: &(dyn NeighborIndex + Sync), seeds: Vec<Vec<f64>>, bandwidth: f64, reach: f64) -> Vec<(Vec<f64>, usize)> {
+        let stop_condition = self.tolerance * bandwidth;
+
+        seeds.into_par_iter().filter_map(|seed| {
+            let mut mean = seed;
+            let mut neighbors = index.within(&mean, reach);
+            if neighbors.is_empty() {
+                return None
+            }
+
+            for _ in 0..self.max_iterations {
+                let updated_mean = self.shift(points, &neighbors, bandwidth);
+                let updated_neighbors = index.within(&updated_mean, reach);
+                if updated_neighbors.is_empty() {
+                    break;
+                }
+
+                let change = Euclidean::distance(&mean, &updated_mean);
+                mean = updated_mean;
+                neighbors = updated_neighbors;
+
+                if change <= stop_condition {
+                    break;
+                }
+            }
+
+            let intensity = index.within(&mean, bandwidth).len();
+            Some((mean, intensity))
+        }).collect()
+    }
+
+    /// Mean distance from every point to its k-th nearest neighbour, with k a quantile of the number of points
+    pub fn estimate_bandwidth(points: &[Point], quantile: f64) -> f64 {
+        let k = ((points.len() as f64 * quantile) as usize).max(1).min(points.len());
+        let index = NeighborSearch::KDTree.build::<Euclidean>(points, f64::INFINITY);
+
+        points.par_iter().map(|p| {
+            match index.nearest(p.coordinates(), k).last() {
+                Some(&(_, distance)) => distance,
+                None => 0.0
+            }
+        }).sum::<f64>() / points.len() as f64
+    }
+
+    fn seeds(&self, points: &[Point], bandwidth: f64) -> Vec<Vec<f64>> {
+        if !self.bin_seeding {
+            return points.iter().map(|p| p.coordinates().to_vec()).collect()
+        }
+
+        let bins = points.iter().fold(HashMap::new(), |mut bins, p| {
+            let bin: Vec<i64> = p.coordinates().iter().map(|x| (x / bandwidth).round() as i64).collect();
+            *bins.entry(bin).or_insert(0) += 1;
+            bins
+        });
+
+        let seeds: Vec<Vec<f64>> = bins.into_iter().filter(|&(_, frequency)| frequency >= self.min_bin_frequency).map(|(bin, _)| {
+            bin.into_iter().map(|b| b as f64 * bandwidth).collect()
+        }).collect();
+
+        // Binning did not reduce the number of seeds, so fall back to the points themselves
+        match seeds.len() == points.len() || seeds.is_empty() {
+            true => points.iter().map(|p| p.coordinates().to_vec()).collect(),
+            false => seeds
+        }
+    }
+
+    #[inline]
+    fn shift(&self, points: &[Point], neighbors: &[(usize, f64)], bandwidth: f64) -> Vec<f64> {
+        let dimension = points[0].coordinates().len();
+
+        let (sum, total_weight) = neighbors.iter().fold((vec![0.0; dimension], 0.0), |(mut sum, total_weight), &(index_p, distance)| {
+            let weight = match self.kernel {
+                Flat => 1.0,
+                Gaussian => (-(distance * distance) / (2.0 * bandwidth * bandwidth)).exp()
+            };
+
+            for (s, x) in sum.iter_mut().zip(points[index_p].coordinates().iter()) {
+                *s += weight * x;
+            }
+
+            (sum, total_weight + weight)
+        });
+
+        sum.into_iter().map(|s| s / total_weight).collect()
+    }
+
+    /// Keeps the most intense modes, dropping any mode within the bandwidth of a more intense one
+    fn merge_modes(mut modes: Vec<(Vec<f64>, usize)>, bandwidth: f64) -> Vec<Vec<f64>> {
+        modes.sort_by(|a, b| b.1.cmp(&a.1));
+
+        modes.into_iter().fold(vec![], |mut centroids: Vec<Vec<f64>>, (mode, _)| {
+            if centroids.iter().all(|c| Euclidean::distance(c, &mode) > bandwidth) {
+                centroids.push(mode);
+            }
+
+            centroids
+        })
+    }
+
+    #[inline]
+    fn closest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
+        match centroids.iter().enumerate().map(|(index_c, c)| {
+            (index_c, SquaredEuclidean::distance(point, c))
+        }).min_by(|&(_, a), &(_, b)| {
+            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
+        }) {
+            Some(closest) => closest,
+            None => panic!()
+        }
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// The bandwidth in use, estimated during `run` unless it was set
+    pub fn bandwidth(&self) -> Option<f64> { self.bandwidth }
+
+    pub fn set_bandwidth(self, bandwidth: f64) -> Self {
+        MeanShift { bandwidth: Some(bandwidth), .. self }
+    }
+
+    /// Quantile of the number of points used as k when estimating the bandwidth
+    pub fn set_quantile(self, quantile: f64) -> Self {
+        MeanShift { quantile, .. self }
+    }
+
+    pub fn set_kernel(self, kernel: Kernel) -> Self {
+        MeanShift { kernel, .. self }
+    }
+
+    pub fn set_bin_seeding(self, bin_seeding: bool) -> Self {
+        MeanShift { bin_seeding, .. self }
+    }
+
+    pub fn set_min_bin_frequency(self, min_bin_frequency: usize) -> Self {
+        MeanShift { min_bin_frequency, .. self }
+    }
+
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        MeanShift { max_iterations, .. self }
+    }
+
+    pub fn set_tolerance(self, tolerance: f64) -> Self {
+        MeanShift { tolerance, .. self }
+    }
+
+    pub fn set_neighbor_search(self, neighbor_search: NeighborSearch) -> Self {
+        MeanShift { neighbor_search, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_mean_shift() {
+        let points = blobs();
+
+        for kernel in [Flat, Gaussian].iter() {
+            let output = MeanShift::new().set_bandwidth(1.0).set_kernel(*kernel).run(&points);
+
+            assert_eq!(3, output.centroids().len());
+            for blob in 0..3 {
+                assert!(output.assignments()[blob * 100..(blob + 1) * 100].iter().all(|a| *a == output.assignments()[blob * 100]));
+            }
+        }
+    }
+
+    #[test]
+    fn can_run_mean_shift_with_estimated_bandwidth_and_bin_seeding() {
+        let points = blobs();
+        let output = MeanShift::new().set_bin_seeding(true).run(&points);
+
+        assert!(output.bandwidth().unwrap() > 0.0);
+        assert_eq!(3, output.centroids().len());
+    }
+
+    #[test]
+    fn bin_seeding_falls_back_to_points() {
+        // Every point falls in the bin of the origin, which is farther than the bandwidth from all of them
+        let points: Vec<Point> = (0..10).map(|i| Point::new(vec![0.45 + 0.001 * i as f64; 8])).collect();
+        let output = MeanShift::new().set_bandwidth(1.0).set_bin_seeding(true).run(&points);
+
+        assert_eq!(1, output.centroids().len());
+        assert!(output.assignments().iter().all(|a| *a == 0));
+    }
+}
@@ -7,6 +7,8 @@ use std::usize;
 use point::Point;
 use statistics::distance::{Distance, SquaredEuclidean};
 use statistics::statistics::Statistics;
+use neighbors::{NeighborIndex, assign_nearest};
+use rayon::prelude::*;
 use clustering::kmeans::*;
 
 pub struct MiniBatchKMeans {
@@ -86,6 +88,16 @@ impl MiniBatchKMeans {
         }
     }
 
+    pub fn predict(&self, points: &[Point]) -> Vec<usize> {
+        let centroids: Vec<Vec<f64>> = self.centroids.iter().map(|c| c.coordinates().to_vec()).collect();
+        points.par_iter().map(|p| Self::closest_centroid(p.coordinates(), centroids.as_slice()).0).collect()
+    }
+
+    /// Assigns points to their closest centroid using an index built over `centroids()`, which must not be empty
+    pub fn predict_with_index<I: NeighborIndex + Sync>(&self, points: &[Point], index: &I) -> Vec<usize> {
+        assign_nearest(points, index)
+    }
+
     #[inline]
     fn closest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
         match centroids.iter().enumerate().map(|(index_c, c)| {
@@ -0,0 +1,348 @@
+// http://www.dbs.ifi.lmu.de/Publikationen/Papers/OPTICS.pdf
+
+use std::cmp::Ordering;
+use std::usize;
+use std::f64;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::NeighborSearch;
+use rayon::prelude::*;
+
+pub struct OPTICS {
+    ordering: Vec<usize>,
+    reachability: Vec<f64>,
+    core_distances: Vec<f64>,
+    predecessors: Vec<Option<usize>>,
+    max_eps: f64,
+    min_points: usize,
+    neighbor_search: NeighborSearch
+}
+
+impl Default for OPTICS {
+    fn default() -> OPTICS {
+        OPTICS {
+            ordering: vec![],
+            reachability: vec![],
+            core_distances: vec![],
+            predecessors: vec![],
+            max_eps: f64::INFINITY,
+            min_points: 5,
+            neighbor_search: NeighborSearch::BruteForce
+        }
+    }
+}
+
+impl OPTICS {
+    pub fn new() -> Self {
+        OPTICS::default()
+    }
+
+    pub fn run<D: Distance>(self, points: &[Point]) -> Self {
+        let n = points.len();
+        let index = self.neighbor_search.build::<D>(points, self.max_eps);
+        let k = self.min_points.max(1);
+
+        let core_distances: Vec<f64> = points.par_iter().map(|p| {
+            let neighbors = index.nearest(p.coordinates(), k);
+
+            match neighbors.last() {
+                Some(&(_, distance)) if neighbors.len() == k && distance <= self.max_eps => distance,
+                _ => f64::INFINITY
+            }
+        }).collect();
+
+        let mut reachability = vec![f64::INFINITY; n];
+        let mut predecessors: Vec<Option<usize>> = vec![None; n];
+        let mut processed = vec![false; n];
+        let mut ordering = Vec::with_capacity(n);
+
+        for _ in 0..n {
+            // The next point is the unprocessed one with the smallest reachability, ties broken by index
+            let current = (0..n).filter(|index_p| !processed[*index_p]).min_by(|a, b| {
+                reachability[*a].partial_cmp(&reachability[*b]).unwrap_or(Ordering::Equal)
+            }).unwrap();
+
+            processed[current] = true;
+            ordering.push(current);
+
+            if core_distances[current].is_infinite() {
+                continue;
+            }
+
+            for (neighbor, distance) in index.within(points[current].coordinates(), self.max_eps) {
+                if processed[neighbor] {
+                    continue;
+                }
+
+                let reachability_distance = distance.max(core_distances[current]);
+                if reachability_distance < reachability[neighbor] {
+                    reachability[neighbor] = reachability_distance;
+                    predecessors[neighbor] = Some(current);
+                }
+            }
+        }
+
+        OPTICS {
+            ordering,
+            reachability,
+            core_distances,
+            predecessors,
+            .. self
+        }
+    }
+
+    /// Reachability distances in cluster order, infinite where undefined
+    pub fn reachability_plot(&self) -> Vec<f64> {
+        self.ordering.iter().map(|index_p| self.reachability[*index_p]).collect()
+    }
+
+    /// DBSCAN clustering at `eps`, which should not exceed `max_eps`. Noise is `None`.
+    pub fn extract_dbscan(&self, eps: f64) -> Vec<Option<usize>> {
+        let mut assignments = vec![None; self.ordering.len()];
+        let mut current: Option<usize> = None;
+        let mut no_clusters = 0;
+
+        for &index_p in self.ordering.iter() {
+            if self.reachability[index_p] > eps {
+                current = match self.core_distances[index_p] <= eps {
+                    true => {
+                        no_clusters += 1;
+                        Some(no_clusters - 1)
+                    },
+                    false => None
+                };
+            }
+
+            assignments[index_p] = current;
+        }
+
+        assignments
+    }
+
+    /// Flat clustering from the xi steep-area method: every point gets the innermost
+    /// cluster of `xi_clusters` not overlapping an already labelled one. Noise is `None`.
+    pub fn extract_xi(&self, xi: f64, min_cluster_size: usize) -> Vec<Option<usize>> {
+        let mut ordered_assignments: Vec<Option<usize>> = vec![None; self.ordering.len()];
+        let mut no_clusters = 0;
+
+        for (start, end) in self.xi_clusters(xi, min_cluster_size) {
+            if ordered_assignments[start..end + 1].iter().all(|a| a.is_none()) {
+                for a in ordered_assignments[start..end + 1].iter_mut() {
+                    *a = Some(no_clusters);
+                }
+
+                no_clusters += 1;
+            }
+        }
+
+        let mut assignments = vec![None; self.ordering.len()];
+        for (position, &index_p) in self.ordering.iter().enumerate() {
+            assignments[index_p] = ordered_assignments[position];
+        }
+
+        assignments
+    }
+
+    /// Hierarchy of clusters found by the xi method as inclusive (start, end) positions in the
+    /// cluster order, nested clusters listed before the clusters containing them
+    pub fn xi_clusters(&self, xi: f64, min_cluster_size: usize) -> Vec<(usize, usize)> {
+        let n = self.ordering.len();
+        let mut plot = self.reachability_plot();
+        plot.push(f64::INFINITY);
+
+        let xi_complement = 1.0 - xi;
+        let ratios: Vec<f64> = (0..n).map(|i| plot[i] / plot[i + 1]).collect();
+        let steep_upward: Vec<bool> = ratios.iter().map(|r| *r <= xi_complement).collect();
+        let steep_downward: Vec<bool> = ratios.iter().map(|r| *r >= 1.0 / xi_complement).collect();
+        let downward: Vec<bool> = ratios.iter().map(|r| *r > 1.0).collect();
+        let upward: Vec<bool> = ratios.iter().map(|r| *r < 1.0).collect();
+
+        // Steep down areas as (start, end, maximum in between)
+        let mut steep_down_areas: Vec<(usize, usize, f64)> = vec![];
+        let mut clusters = vec![];
+        let mut index = 0;
+        let mut mib: f64 = 0.0;
+
+        for steep_index in (0..n).filter(|i| steep_upward[*i] || steep_downward[*i]) {
+            if steep_index < index {
+                continue;
+            }
+
+            mib = plot[index..steep_index + 1].iter().fold(mib, |max, r| max.max(*r));
+            steep_down_areas = Self::filter_steep_down_areas(steep_down_areas, mib, xi_complement, &plot);
+
+            if steep_downward[steep_index] {
+                let end = Self::extend_region(&steep_downward, &upward, steep_index, self.min_points);
+                steep_down_areas.push((steep_index, end, 0.0));
+                index = end + 1;
+                mib = plot[index];
+                continue;
+            }
+
+            let up_start = steep_index;
+            let up_end = Self::extend_region(&steep_upward, &downward, up_start, self.min_points);
+            index = up_end + 1;
+            mib = plot[index];
+
+            let mut up_clusters = vec![];
+            for &(down_start, down_end, down_mib) in steep_down_areas.iter() {
+                let mut start = down_start;
+                let mut end = up_end;
+
+                if plot[end + 1] * xi_complement < down_mib {
+                    continue;
+                }
+
+                let down_max = plot[down_start];
+                if down_max * xi_complement >= plot[end + 1] {
+                    while plot[start + 1] > plot[end + 1] && start < down_end {
+                        start += 1;
+                    }
+                } else if plot[end + 1] * xi_complement >= down_max {
+                    while plot[end - 1] > down_max && end > up_start {
+                        end -= 1;
+                    }
+                }
+
+                let (start, end) = match self.correct_predecessor(&plot, start, end) {
+                    Some(corrected) => corrected,
+                    None => continue
+                };
+
+                if end + 1 - start < min_cluster_size || start > down_end || end < up_start {
+                    continue;
+                }
+
+                up_clusters.push((start, end));
+            }
+
+            up_clusters.reverse();
+            clusters.extend(up_clusters);
+        }
+
+        clusters
+    }
+
+    fn filter_steep_down_areas(steep_down_areas: Vec<(usize, usize, f64)>, mib: f64, xi_complement: f64, plot: &[f64]) -> Vec<(usize, usize, f64)> {
+        if mib.is_infinite() {
+            return vec![]
+        }
+
+        steep_down_areas.into_iter().filter(|&(start, _, _)| mib <= plot[start] * xi_complement).map(|(start, end, down_mib)| {
+            (start, end, down_mib.max(mib))
+        }).collect()
+    }
+
+    // Grows a steep area while it contains at most `min_points` consecutive non-steep points going the same way
+    fn extend_region(steep: &[bool], same_direction: &[bool], start: usize, min_points: usize) -> usize {
+        let mut non_steep = 0;
+        let mut end = start;
+
+        for index in start..steep.len() {
+            if steep[index] {
+                non_steep = 0;
+                end = index;
+            } else if !same_direction[index] {
+                non_steep += 1;
+
+                if non_steep > min_points {
+                    break;
+                }
+            } else {
+                return end
+            }
+        }
+
+        end
+    }
+
+    // Shrinks the cluster end until its last point was reached from within the cluster
+    fn correct_predecessor(&self, plot: &[f64], start: usize, mut end: usize) -> Option<(usize, usize)> {
+        while start < end {
+            if plot[start] > plot[end] {
+                return Some((start, end))
+            }
+
+            let predecessor = self.predecessors[self.ordering[end]];
+            if self.ordering[start..end].iter().any(|index_p| Some(*index_p) == predecessor) {
+                return Some((start, end))
+            }
+
+            end -= 1;
+        }
+
+        None
+    }
+
+    pub fn ordering(&self) -> &[usize] { &self.ordering }
+
+    /// Reachability distance of every point, infinite where undefined
+    pub fn reachability(&self) -> &[f64] { &self.reachability }
+
+    /// Core distance of every point, infinite for points that are not core within `max_eps`
+    pub fn core_distances(&self) -> &[f64] { &self.core_distances }
+
+    pub fn predecessors(&self) -> &[Option<usize>] { &self.predecessors }
+
+    pub fn set_max_eps(self, max_eps: f64) -> Self {
+        OPTICS { max_eps, .. self }
+    }
+
+    pub fn set_min_points(self, min_points: usize) -> Self {
+        OPTICS { min_points, .. self }
+    }
+
+    pub fn set_neighbor_search(self, neighbor_search: NeighborSearch) -> Self {
+        OPTICS { neighbor_search, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statistics::distance::Euclidean;
+    use clustering::dbscan::DBSCAN;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 100, 0.15, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_optics() {
+        let points = blobs();
+        let output = OPTICS::new().run::<Euclidean>(&points);
+
+        assert_eq!(points.len(), output.ordering().len());
+        assert_eq!(3, output.reachability_plot().iter().filter(|r| **r > 2.0).count());
+    }
+
+    #[test]
+    fn dbscan_extraction_matches_dbscan_core_points() {
+        let points = blobs();
+        let output = OPTICS::new().set_min_points(5).run::<Euclidean>(&points);
+        let expected = DBSCAN::new().set_eps(0.3).set_min_points(5).run::<Euclidean>(&points);
+
+        let assignments = output.extract_dbscan(0.3);
+        let no_clusters = assignments.iter().filter_map(|a| *a).max().map_or(0, |c| c + 1);
+
+        assert_eq!(expected.no_clusters(), no_clusters);
+        for index_p in expected.core_points() {
+            assert!(assignments[index_p].is_some());
+        }
+    }
+
+    #[test]
+    fn can_extract_xi_clusters() {
+        let points = blobs();
+        let output = OPTICS::new().run::<Euclidean>(&points);
+
+        let assignments = output.extract_xi(0.3, 50);
+
+        for blob in 0..3 {
+            let first = assignments[blob * 100];
+            assert!(first.is_some());
+            assert!(assignments[blob * 100..(blob + 1) * 100].iter().filter(|a| **a == first).count() > 90);
+        }
+    }
+}
@@ -0,0 +1,355 @@
+// https://papers.nips.cc/paper/2092-on-spectral-clustering-analysis-and-an-algorithm.pdf
+// https://www1.icsi.berkeley.edu/~stellayu/publication/doc/2003kwayICCV.pdf
+
+use rand;
+use rand::distributions::{IndependentSample, Range};
+use std::cmp::Ordering;
+use std::f64;
+use std::collections::HashMap;
+use point::Point;
+use clustering::kmeans::{KMeans, KMeansInitialization};
+use statistics::distance::Distance;
+use statistics::statistics::Statistics;
+use neighbors::NeighborSearch;
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum Affinity {
+    /// Gaussian kernel exp(-gamma * d^2) between every pair of points
+    RBF(f64),
+    /// Symmetrised connectivity of the k nearest neighbours of every point
+    NearestNeighbors(usize)
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum LabelAssignment {
+    /// KMeans on the row-normalised embedding
+    KMeans,
+    /// Discretisation of the embedding by iterative rotation
+    Discretize
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum EigenSolver {
+    /// Full Jacobi eigen-decomposition, cubic in the number of points
+    Dense,
+    /// Lanczos iteration over the sparse affinity graph
+    Lanczos
+}
+
+pub struct SpectralClustering {
+    assignments: Vec<usize>,
+    embedding: Vec<Point>,
+    eigenvalues: Vec<f64>,
+    affinity: Affinity,
+    label_assignment: LabelAssignment,
+    eigen_solver: EigenSolver,
+    neighbor_search: NeighborSearch,
+    max_iterations: usize
+}
+
+impl Default for SpectralClustering {
+    fn default() -> SpectralClustering {
+        SpectralClustering {
+            assignments: vec![],
+            embedding: vec![],
+            eigenvalues: vec![],
+            affinity: Affinity::RBF(1.0),
+            label_assignment: LabelAssignment::KMeans,
+            eigen_solver: EigenSolver::Dense,
+            neighbor_search: NeighborSearch::BruteForce,
+            max_iterations: 30
+        }
+    }
+}
+
+impl SpectralClustering {
+    pub fn new() -> Self {
+        SpectralClustering::default()
+    }
+
+    pub fn run<D: Distance>(self, points: &[Point], no_clusters: usize) -> Self {
+        let affinity = match self.affinity {
+            Affinity::RBF(gamma) => points.par_iter().map(|p| {
+                points.iter().enumerate().map(|(index_q, q)| {
+                    let distance = D::distance(p.coordinates(), q.coordinates());
+                    (index_q, (-gamma * distance * distance).exp())
+                }).collect()
+            }).collect(),
+            Affinity::NearestNeighbors(k) => {
+                let index = self.neighbor_search.build::<D>(points, f64::INFINITY);
+                let neighbors: Vec<Vec<(usize, f64)>> = points.par_iter().map(|p| index.nearest(p.coordinates(), k + 1)).collect();
+
+                // 0.5 * (A + A^T) with A the directed neighbour connectivity, self excluded
+                let mut edges: Vec<HashMap<usize, f64>> = vec![HashMap::new(); points.len()];
+                for (index_p, row) in neighbors.into_iter().enumerate() {
+                    for (index_q, _) in row.into_iter().filter(|&(index_q, _)| index_q != index_p).take(k) {
+                        *edges[index_p].entry(index_q).or_insert(0.0) += 0.5;
+                        *edges[index_q].entry(index_p).or_insert(0.0) += 0.5;
+                    }
+                }
+
+                edges.into_iter().map(|row| {
+                    let mut row: Vec<(usize, f64)> = row.into_iter().collect();
+                    row.sort_by(|a, b| a.0.cmp(&b.0));
+                    row
+                }).collect()
+            }
+        };
+
+        self.cluster(affinity, no_clusters)
+    }
+
+    /// Clusters from a user-supplied symmetric, non-negative affinity matrix
+    pub fn run_precomputed(self, affinity: &[Vec<f64>], no_clusters: usize) -> Self {
+        let affinity = affinity.iter().map(|row| {
+            row.iter().cloned().enumerate().filter(|&(_, a)| a != 0.0).collect()
+        }).collect();
+
+        self.cluster(affinity, no_clusters)
+    }
+
+    fn cluster(self, affinity: Vec<Vec<(usize, f64)>>, no_clusters: usize) -> Self {
+        let n = affinity.len();
+        if n == 0 || no_clusters == 0 {
+            return SpectralClustering { assignments: vec![], embedding: vec![], eigenvalues: vec![], .. self }
+        }
+
+        // The top eigenvectors of D^-1/2 A D^-1/2 are the bottom ones of the normalised Laplacian
+        let inverse_sqrt_degrees: Vec<f64> = affinity.iter().map(|row| {
+            match row.iter().map(|&(_, a)| a).sum::<f64>() {
+                degree if degree > 0.0 => 1.0 / degree.sqrt(),
+                _ => 0.0
+            }
+        }).collect();
+
+        let normalized: Vec<Vec<(usize, f64)>> = affinity.into_iter().enumerate().map(|(i, row)| {
+            row.into_iter().map(|(j, a)| (j, a * inverse_sqrt_degrees[i] * inverse_sqrt_degrees[j])).collect()
+        }).collect();
+
+        let (eigenvalues, eigenvectors) = match self.eigen_solver {
+            EigenSolver::Dense => {
+                let dense: Vec<Vec<f64>> = normalized.iter().map(|row| {
+                    row.iter().fold(vec![0.0; n], |mut dense, &(j, a)| {
+                        dense[j] = a;
+                        dense
+                    })
+                }).collect();
+
+                let (values, vectors) = Statistics::symmetric_eigen(&dense);
+                (values.into_iter().take(no_clusters).collect::<Vec<f64>>(), vectors.into_iter().take(no_clusters).collect::<Vec<Vec<f64>>>())
+            },
+            EigenSolver::Lanczos => {
+                Statistics::lanczos(n, |x| {
+                    normalized.par_iter().map(|row| row.iter().map(|&(j, a)| a * x[j]).sum()).collect()
+                }, no_clusters, (10 * no_clusters).max(100))
+            }
+        };
+
+        let embedding: Vec<Vec<f64>> = (0..n).map(|i| {
+            let row: Vec<f64> = eigenvectors.iter().map(|v| v[i]).collect();
+            let length = row.iter().map(|x| x * x).sum::<f64>().sqrt();
+
+            match length > 0.0 {
+                true => row.into_iter().map(|x| x / length).collect(),
+                false => row
+            }
+        }).collect();
+
+        let embedding: Vec<Point> = embedding.into_iter().map(|row| Point::new(row)).collect();
+
+        let assignments = match self.label_assignment {
+            LabelAssignment::KMeans => KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus)
+                                                    .set_max_iterations(100)
+                                                    .run(&embedding, no_clusters)
+                                                    .assignments()
+                                                    .to_vec(),
+            LabelAssignment::Discretize => self.discretize(&embedding, no_clusters)
+        };
+
+        SpectralClustering {
+            assignments,
+            embedding,
+            eigenvalues: eigenvalues.into_iter().map(|value| 1.0 - value).collect(),
+            .. self
+        }
+    }
+
+    // Searches the rotation of the embedding closest to a discrete cluster indicator matrix
+    fn discretize(&self, embedding: &[Point], no_clusters: usize) -> Vec<usize> {
+        let n = embedding.len();
+        let mut rng = rand::thread_rng();
+
+        // Initial rotation from rows that are as orthogonal to each other as possible
+        let mut rotation: Vec<Vec<f64>> = vec![embedding[Range::new(0, n).ind_sample(&mut rng)].coordinates().to_vec()];
+        let mut overlap = vec![0.0; n];
+        for _ in 1..no_clusters {
+            for (o, e) in overlap.iter_mut().zip(embedding.iter()) {
+                *o += Self::dot(e.coordinates(), &rotation[rotation.len() - 1]).abs();
+            }
+
+            let next = (0..n).min_by(|a, b| overlap[*a].partial_cmp(&overlap[*b]).unwrap_or(Ordering::Equal)).unwrap();
+            rotation.push(embedding[next].coordinates().to_vec());
+        }
+
+        let mut assignments = vec![0; n];
+        let mut last_ncut = f64::INFINITY;
+
+        for _ in 0..self.max_iterations {
+            assignments = embedding.par_iter().map(|e| {
+                (0..no_clusters).max_by(|a, b| {
+                    Self::dot(e.coordinates(), &rotation[*a]).partial_cmp(&Self::dot(e.coordinates(), &rotation[*b])).unwrap_or(Ordering::Equal)
+                }).unwrap()
+            }).collect();
+
+            // M = V^T E with V the indicator matrix of the current assignments
+            let mut m = vec![vec![0.0; no_clusters]; no_clusters];
+            for (e, a) in embedding.iter().zip(assignments.iter()) {
+                for (m_l, e_l) in m[*a].iter_mut().zip(e.coordinates().iter()) {
+                    *m_l += e_l;
+                }
+            }
+
+            // The closest rotation is the orthogonal polar factor M (M^T M)^-1/2, whose singular values sum to the objective
+            let gram: Vec<Vec<f64>> = (0..no_clusters).map(|i| (0..no_clusters).map(|j| {
+                m.iter().map(|row| row[i] * row[j]).sum()
+            }).collect()).collect();
+
+            let (values, vectors) = Statistics::symmetric_eigen(&gram);
+            let ncut = 2.0 * (n as f64 - values.iter().map(|v| v.max(0.0).sqrt()).sum::<f64>());
+
+            if (last_ncut - ncut).abs() < 1e-10 {
+                break;
+            }
+
+            last_ncut = ncut;
+
+            let inverse_sqrt_gram: Vec<Vec<f64>> = (0..no_clusters).map(|i| (0..no_clusters).map(|j| {
+                values.iter().zip(vectors.iter()).filter(|&(value, _)| *value > 1e-12).map(|(value, vector)| vector[i] * vector[j] / value.sqrt()).sum()
+            }).collect()).collect();
+
+            // Row j of the new rotation is row j of M (M^T M)^-1/2
+            rotation = (0..no_clusters).map(|j| (0..no_clusters).map(|l| {
+                (0..no_clusters).map(|i| m[j][i] * inverse_sqrt_gram[i][l]).sum()
+            }).collect()).collect();
+        }
+
+        assignments
+    }
+
+    #[inline]
+    fn dot(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    /// Row-normalised spectral embedding of every point
+    pub fn embedding(&self) -> &[Point] { &self.embedding }
+
+    /// Smallest eigenvalues of the normalised Laplacian, increasing
+    pub fn eigenvalues(&self) -> &[f64] { &self.eigenvalues }
+
+    pub fn set_affinity(self, affinity: Affinity) -> Self {
+        SpectralClustering { affinity, .. self }
+    }
+
+    pub fn set_label_assignment(self, label_assignment: LabelAssignment) -> Self {
+        SpectralClustering { label_assignment, .. self }
+    }
+
+    pub fn set_eigen_solver(self, eigen_solver: EigenSolver) -> Self {
+        SpectralClustering { eigen_solver, .. self }
+    }
+
+    pub fn set_neighbor_search(self, neighbor_search: NeighborSearch) -> Self {
+        SpectralClustering { neighbor_search, .. self }
+    }
+
+    /// Maximum number of rotation updates when discretising
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        SpectralClustering { max_iterations, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use statistics::distance::{Euclidean, SquaredEuclidean};
+
+    fn rings() -> Vec<Point> {
+        let mut rng = rand::thread_rng();
+
+        [1.0, 3.0].iter().flat_map(|&radius| {
+            (0..100).map(|_| {
+                let angle = rng.next_f64() * 2.0 * f64::consts::PI;
+                let r = radius + 0.1 * (rng.next_f64() - 0.5);
+                Point::new(vec![r * angle.cos(), r * angle.sin()])
+            }).collect::<Vec<Point>>()
+        }).collect()
+    }
+
+    fn separates_rings(assignments: &[usize]) -> bool {
+        assignments[..100].iter().all(|a| *a == assignments[0]) &&
+            assignments[100..].iter().all(|a| *a == assignments[100]) &&
+            assignments[0] != assignments[100]
+    }
+
+    #[test]
+    fn can_cluster_rings_with_nearest_neighbors_and_lanczos() {
+        let points = rings();
+        let output = SpectralClustering::new().set_affinity(Affinity::NearestNeighbors(10))
+                                              .set_eigen_solver(EigenSolver::Lanczos)
+                                              .run::<Euclidean>(&points, 2);
+
+        assert!(separates_rings(output.assignments()));
+        assert!(output.eigenvalues()[0].abs() < 1e-6);
+    }
+
+    #[test]
+    fn can_cluster_rings_with_rbf_and_discretize() {
+        let points = rings();
+        let output = SpectralClustering::new().set_affinity(Affinity::RBF(5.0))
+                                              .set_label_assignment(LabelAssignment::Discretize)
+                                              .run::<Euclidean>(&points, 2);
+
+        assert!(separates_rings(output.assignments()));
+    }
+
+    #[test]
+    fn can_use_nearest_neighbors_with_any_metric() {
+        let points = rings();
+        let output = SpectralClustering::new().set_affinity(Affinity::NearestNeighbors(10))
+                                              .run::<SquaredEuclidean>(&points, 2);
+
+        assert!(separates_rings(output.assignments()));
+    }
+
+    #[test]
+    fn can_run_without_points_or_clusters() {
+        let output = SpectralClustering::new().set_affinity(Affinity::NearestNeighbors(10)).run::<Euclidean>(&[], 2);
+        assert!(output.assignments().is_empty());
+        assert!(output.embedding().is_empty());
+        assert!(output.eigenvalues().is_empty());
+
+        let output = SpectralClustering::new().set_label_assignment(LabelAssignment::Discretize).run_precomputed(&[], 2);
+        assert!(output.assignments().is_empty());
+
+        for label_assignment in [LabelAssignment::KMeans, LabelAssignment::Discretize].iter() {
+            let output = SpectralClustering::new().set_label_assignment(*label_assignment).run::<Euclidean>(&rings(), 0);
+            assert!(output.assignments().is_empty());
+            assert!(output.embedding().is_empty());
+        }
+    }
+
+    #[test]
+    fn can_cluster_precomputed_affinity() {
+        let affinity: Vec<Vec<f64>> = (0..6).map(|i| (0..6).map(|j| if i / 3 == j / 3 { 1.0 } else { 0.01 }).collect()).collect();
+        let output = SpectralClustering::new().run_precomputed(&affinity, 2);
+
+        assert!(output.assignments()[..3].iter().all(|a| *a == output.assignments()[0]));
+        assert!(output.assignments()[3..].iter().all(|a| *a == output.assignments()[3]));
+        assert!(output.assignments()[0] != output.assignments()[3]);
+    }
+}
@@ -0,0 +1,41 @@
+use rand::{SeedableRng, StdRng};
+use rand::distributions::{IndependentSample, Normal};
+use super::Dataset;
+use point::Point;
+
+/// Generates no_points points around each center, with gaussian noise of standard deviation spread on every
+/// coordinate. The points are ordered blob by blob and targeted by the index of their center, and the same seed
+/// always gives the same points.
+pub fn make_blobs(centers: &[&[f64]], no_points: usize, spread: f64, seed: usize) -> Dataset<Vec<Point>, Vec<usize>> {
+    let mut rng: StdRng = SeedableRng::from_seed(&[seed][..]);
+    let normal = Normal::new(0.0, spread);
+
+    let data = centers.iter().flat_map(|center| {
+        (0..no_points).map(|_| {
+            Point::new(center.iter().map(|x| x + normal.ind_sample(&mut rng)).collect())
+        }).collect::<Vec<Point>>()
+    }).collect();
+
+    let target = (0..centers.len()).flat_map(|index| vec![index; no_points]).collect();
+
+    Dataset {
+        data,
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_gives_same_blobs() {
+        let centers: &[&[f64]] = &[&[0.0, 0.0], &[5.0, 5.0]];
+        let blobs = make_blobs(centers, 50, 0.3, 7);
+
+        assert_eq!(blobs.data().len(), 100);
+        assert_eq!(blobs.target()[49], 0);
+        assert_eq!(blobs.target()[50], 1);
+        assert!(blobs.data().iter().zip(make_blobs(centers, 50, 0.3, 7).data()).all(|(p, q)| p.coordinates() == q.coordinates()));
+    }
+}
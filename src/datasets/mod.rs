@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+/// Module for generated blobs.
+pub mod blobs;
 /// Module for iris dataset.
 pub mod iris;
 /// Module for trees dataset.
@@ -1,6 +1,4 @@
-use std::cmp::Ordering;
 use std::f64;
-use std::ops::Deref;
 use point::Point;
 use statistics::distance::{Distance, SquaredEuclidean};
 
@@ -8,104 +6,94 @@ use statistics::distance::{Distance, SquaredEuclidean};
 pub struct Cluster {
     distance: f64,
     mean: Vec<f64>,
-    points: Vec<Point>,
+    points: Vec<usize>,
     representative: Vec<Point>,
-    closest: Option<Box<Cluster>>
+    closest: Option<usize>
 }
 
 impl Cluster {
-    pub fn new(point: Point) -> Cluster {
+    pub fn new(index: usize, point: &Point) -> Cluster {
         Cluster {
             distance: f64::INFINITY,
             mean: point.coordinates().to_vec(),
-            points: vec![point.clone()],
+            points: vec![index],
             representative: vec![point.clone()],
             closest: None
         }
     }
 
-    pub fn merge(&self, number_represent_points: usize, compression: f64) -> Option<Cluster> {
+    /// Union with `other`, represented by up to `number_represent_points` well scattered points
+    /// moved towards the mean by `compression`
+    pub fn merge(&self, other: &Cluster, points: &[Point], number_represent_points: usize, compression: f64) -> Cluster {
         let mut merged_points = self.points.clone();
-        let b = match self.closest.clone() {
-            None => return None,
-            Some(cluster) => {
-                merged_points.extend(cluster.clone().points);
-                cluster.deref().clone()
-            }
-        };
+        merged_points.extend(other.points.iter().cloned());
 
         let a_len = self.points.len() as f64;
-        let b_len = b.points.len() as f64;
-
-        let merged_mean = match (merged_points.first(), merged_points.last()) {
-            (Some(first), Some(last)) if *first == *last => first.coordinates().to_vec(),
-            _ => (0..self.mean.len()).map(|i| {
-                (a_len * self.mean.get(i).unwrap_or(&0.0) + b_len * b.mean.get(i).unwrap_or(&0.0)) / (a_len + b_len)
-            }).collect::<Vec<f64>>()
-        };
-
-        let mut temp: Vec<Point> = vec![];
-        for index in 0..number_represent_points {
-            let mut maximal_distance = 0.0;
-            let mut maximal_point = None;
-
-            for point in merged_points.clone() {
-                let minimal_distance = match index {
-                    0 => SquaredEuclidean::distance(point.coordinates(), &merged_mean),
-                    _ => temp.iter().map(|p| SquaredEuclidean::distance(point.coordinates(), p.coordinates())).fold(f64::INFINITY, f64::min)
-                };
+        let b_len = other.points.len() as f64;
 
-                if minimal_distance > maximal_distance {
-                    maximal_distance = minimal_distance;
-                    maximal_point = Some(point);
-                }
-            }
+        let merged_mean: Vec<f64> = self.mean.iter().zip(other.mean.iter()).map(|(a, b)| {
+            (a_len * a + b_len * b) / (a_len + b_len)
+        }).collect();
+
+        // Farthest point from the mean first, then the point farthest from those already chosen
+        let mut scattered: Vec<usize> = vec![];
+        let mut minimal_distances: Vec<f64> = merged_points.iter().map(|index_p| {
+            SquaredEuclidean::distance(points[*index_p].coordinates(), &merged_mean)
+        }).collect();
 
-            match maximal_point {
-                Some(point) => {
-                    if !temp.contains(&point) {
-                        temp.push(point);
-                    }
-                },
-                None => ()
+        for _ in 0..number_represent_points.min(merged_points.len()) {
+            let (farthest, _) = minimal_distances.iter().enumerate().fold((0, f64::NEG_INFINITY), |(farthest, maximal_distance), (index, distance)| {
+                if *distance > maximal_distance { (index, *distance) } else { (farthest, maximal_distance) }
+            });
+
+            scattered.push(merged_points[farthest]);
+
+            for (index, distance) in minimal_distances.iter_mut().enumerate() {
+                let to_chosen = SquaredEuclidean::distance(points[merged_points[index]].coordinates(), points[merged_points[farthest]].coordinates());
+                *distance = match scattered.len() {
+                    1 => to_chosen,
+                    _ => distance.min(to_chosen)
+                };
             }
+
+            minimal_distances[farthest] = f64::NEG_INFINITY;
         }
 
-        let merged_rep = temp.iter().map(|p| {
-            let coords = p.coordinates();
-            Point::new((0..coords.len()).map(|i| coords.get(i).unwrap() + compression * (merged_mean.get(i).unwrap() - coords.get(i).unwrap())).collect())
+        let merged_rep = scattered.iter().map(|index_p| {
+            let coords = points[*index_p].coordinates();
+            Point::new(coords.iter().zip(merged_mean.iter()).map(|(x, m)| x + compression * (m - x)).collect())
         }).collect();
 
-        Some(Cluster {
-            distance: 0.0,
-            mean: merged_mean.to_vec(),
+        Cluster {
+            distance: f64::INFINITY,
+            mean: merged_mean,
             points: merged_points,
             representative: merged_rep,
             closest: None
-        })
+        }
     }
 
-    pub fn update_closest(&mut self) {
-
+    /// Squared distance between the closest pair of representatives
+    pub fn distance_to(&self, other: &Cluster) -> f64 {
+        self.representative.iter().flat_map(|a| other.representative.iter().map(move |b| {
+            SquaredEuclidean::distance(a.coordinates(), b.coordinates())
+        })).fold(f64::INFINITY, f64::min)
     }
-}
 
-impl Ord for Cluster {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    pub fn update_closest(&mut self, closest: Option<usize>, distance: f64) {
+        self.closest = closest;
+        self.distance = distance;
     }
-}
 
-impl PartialOrd for Cluster {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.distance.partial_cmp(&other.distance)
-    }
-}
+    /// Squared distance to the closest cluster
+    pub fn distance(&self) -> f64 { self.distance }
 
-impl Eq for Cluster {}
+    pub fn closest(&self) -> Option<usize> { self.closest }
 
-impl PartialEq for Cluster {
-    fn eq(&self, other: &Self) -> bool {
-        self.distance == other.distance
-    }
-}
\ No newline at end of file
+    pub fn mean(&self) -> &[f64] { &self.mean }
+
+    /// Indices of the member points
+    pub fn points(&self) -> &[usize] { &self.points }
+
+    pub fn representative(&self) -> &[Point] { &self.representative }
+}
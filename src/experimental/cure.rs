@@ -1,30 +1,338 @@
 // https://www.cis.upenn.edu/~sudipto/mypapers/cure_final.pdf
 
+use rand;
+use rand::Rng;
+use std::cmp::Ordering;
 use std::collections::BinaryHeap;
+use std::f64;
 use experimental::cluster::Cluster;
+use experimental::kdtree::KDTree;
+use statistics::distance::{Distance, Euclidean, SquaredEuclidean};
 use point::Point;
+use rayon::prelude::*;
 
 pub fn cure(points: Vec<Point>, no_clusters: usize, number_represent_points: usize, compression: f64) -> Vec<Cluster> {
-    let mut clusters = points.into_iter().map(|p| Cluster::new(p)).collect::<BinaryHeap<Cluster>>();
+    CURE::new().set_no_representatives(number_represent_points)
+               .set_compression(compression)
+               .run(&points, no_clusters)
+               .clusters
+}
 
-    while clusters.len() > no_clusters {
-        let merged = match clusters.pop() {
-            Some(cluster) => cluster.merge(number_represent_points, compression),
-            None => break
-        };
+pub struct CURE {
+    assignments: Vec<usize>,
+    clusters: Vec<Cluster>,
+    no_representatives: usize,
+    compression: f64,
+    sample_size: Option<usize>,
+    no_partitions: usize,
+    reduction: usize
+}
+
+impl Default for CURE {
+    fn default() -> CURE {
+        CURE {
+            assignments: vec![],
+            clusters: vec![],
+            no_representatives: 10,
+            compression: 0.3,
+            sample_size: None,
+            no_partitions: 1,
+            reduction: 3
+        }
+    }
+}
+
+/// Heap entry keyed on the distance of a cluster to its closest cluster, smallest first
+struct Candidate {
+    distance: f64,
+    cluster: usize,
+    version: usize
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.partial_cmp(&self.distance).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Eq for Candidate {}
 
-        match merged {
-            Some(cluster) => {
-                clusters = clusters.into_iter().map(|mut c| {
-                    c.update_closest();
-                    c
-                }).collect();
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance
+    }
+}
+
+impl CURE {
+    pub fn new() -> Self {
+        CURE::default()
+    }
 
-                clusters.push(cluster);
+    pub fn run(self, points: &[Point], no_clusters: usize) -> Self {
+        let sample: Vec<usize> = match self.sample_size {
+            Some(sample_size) if sample_size < points.len() => {
+                let mut sample: Vec<usize> = (0..points.len()).collect();
+                rand::thread_rng().shuffle(&mut sample);
+                sample.truncate(sample_size);
+                sample.sort();
+                sample
             },
-            None => panic!()
+            _ => (0..points.len()).collect()
+        };
+
+        // Every partition is pre-clustered down to a fraction of its size, then the partial clusters are clustered together
+        let no_partitions = self.no_partitions.max(1);
+        let partition_size = (sample.len() + no_partitions - 1) / no_partitions;
+        let partial_clusters: Vec<Cluster> = match no_partitions > 1 {
+            true => sample.par_chunks(partition_size.max(1)).flat_map(|partition| {
+                let singletons = partition.iter().map(|index_p| Cluster::new(*index_p, &points[*index_p])).collect();
+                self.agglomerate(points, singletons, (partition.len() / self.reduction.max(1)).max(no_clusters))
+            }).collect(),
+            false => sample.iter().map(|index_p| Cluster::new(*index_p, &points[*index_p])).collect()
+        };
+
+        let clusters = self.agglomerate(points, partial_clusters, no_clusters);
+
+        // Every point joins the cluster of its closest representative
+        let (representatives, owners): (Vec<&Point>, Vec<usize>) = clusters.iter().enumerate().flat_map(|(index_c, cluster)| {
+            cluster.representative().iter().map(move |r| (r, index_c))
+        }).unzip();
+
+        let assignments = {
+            let mut references = representatives.clone();
+            let tree = KDTree::new(references.as_mut_slice());
+
+            points.par_iter().map(|p| owners[tree.nearest::<Euclidean>(p.coordinates(), 1)[0].0]).collect()
+        };
+
+        CURE {
+            assignments,
+            clusters,
+            .. self
+        }
+    }
+
+    // Merges the closest pair of clusters until `no_clusters` remain
+    fn agglomerate(&self, points: &[Point], clusters: Vec<Cluster>, no_clusters: usize) -> Vec<Cluster> {
+        let mut clusters: Vec<Option<Cluster>> = clusters.into_iter().map(Some).collect();
+        let mut versions = vec![0; clusters.len()];
+        let mut alive = clusters.len();
+        let mut heap = BinaryHeap::new();
+
+        let mut representatives: Vec<Point> = vec![];
+        let mut owners: Vec<usize> = vec![];
+        for (index_c, cluster) in clusters.iter().enumerate() {
+            for r in cluster.as_ref().unwrap().representative() {
+                representatives.push(r.clone());
+                owners.push(index_c);
+            }
+        }
+
+        let mut initialized = false;
+
+        // The kd-tree covers a snapshot of the representatives and is rebuilt once enough new ones are pending
+        while alive > no_clusters.max(1) {
+            let mut pending: Vec<(Point, usize)> = vec![];
+
+            {
+                let mut references: Vec<&Point> = representatives.iter().collect();
+                let tree = KDTree::new(references.as_mut_slice());
+                let search = ClosestSearch { clusters: &clusters, tree: &tree, owners: &owners };
+
+                if !initialized {
+                    let initial: Vec<(Option<usize>, f64)> = (0..clusters.len()).map(|index_c| search.closest(index_c, &pending)).collect();
+
+                    for (index_c, (closest, distance)) in initial.into_iter().enumerate() {
+                        heap.push(Candidate { distance, cluster: index_c, version: 0 });
+                        clusters[index_c].as_mut().unwrap().update_closest(closest, distance);
+                    }
+
+                    initialized = true;
+                }
+
+                while alive > no_clusters.max(1) && pending.len() <= representatives.len() / 2 {
+                    let candidate = match heap.pop() {
+                        Some(candidate) => candidate,
+                        None => break
+                    };
+
+                    if clusters[candidate.cluster].is_none() || versions[candidate.cluster] != candidate.version {
+                        continue;
+                    }
+
+                    let u = candidate.cluster;
+                    let v = match clusters[u].as_ref().unwrap().closest() {
+                        Some(v) => v,
+                        None => break
+                    };
+
+                    let merged = clusters[u].as_ref().unwrap().merge(clusters[v].as_ref().unwrap(), points, self.no_representatives, self.compression);
+                    clusters[u] = None;
+                    clusters[v] = None;
+                    alive -= 1;
+
+                    let w = clusters.len();
+                    pending.extend(merged.representative().iter().map(|r| (r.clone(), w)));
+                    clusters.push(Some(merged));
+                    versions.push(0);
+
+                    let search = ClosestSearch { clusters: &clusters, tree: &tree, owners: &owners };
+                    let (closest, distance) = search.closest(w, &pending);
+
+                    let mut updates: Vec<(usize, Option<usize>, f64)> = vec![(w, closest, distance)];
+                    for (index_x, x) in clusters.iter().enumerate().filter(|&(index_x, _)| index_x != w) {
+                        let x = match *x {
+                            Some(ref x) => x,
+                            None => continue
+                        };
+
+                        let to_merged = x.distance_to(clusters[w].as_ref().unwrap());
+
+                        if x.closest() == Some(u) || x.closest() == Some(v) {
+                            let (closest, distance) = search.closest(index_x, &pending);
+                            updates.push((index_x, closest, distance));
+                        } else if to_merged < x.distance() {
+                            updates.push((index_x, Some(w), to_merged));
+                        }
+                    }
+
+                    for (index_x, closest, distance) in updates {
+                        clusters[index_x].as_mut().unwrap().update_closest(closest, distance);
+                        versions[index_x] += 1;
+                        heap.push(Candidate { distance, cluster: index_x, version: versions[index_x] });
+                    }
+                }
+            }
+
+            if pending.is_empty() {
+                break;
+            }
+
+            // The next kd-tree only indexes the representatives of clusters still alive
+            let (live_representatives, live_owners): (Vec<Point>, Vec<usize>) = representatives.into_iter().zip(owners.into_iter())
+                                                                                               .filter(|&(_, owner)| clusters[owner].is_some())
+                                                                                               .chain(pending.into_iter().filter(|&(_, owner)| clusters[owner].is_some()))
+                                                                                               .unzip();
+            representatives = live_representatives;
+            owners = live_owners;
         }
+
+        clusters.into_iter().filter_map(|c| c).collect()
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn clusters(&self) -> &[Cluster] { &self.clusters }
+
+    /// Number of well scattered points representing every cluster
+    pub fn set_no_representatives(self, no_representatives: usize) -> Self {
+        CURE { no_representatives, .. self }
+    }
+
+    /// Fraction of the way representatives move towards the cluster mean
+    pub fn set_compression(self, compression: f64) -> Self {
+        CURE { compression, .. self }
+    }
+
+    /// Number of randomly sampled points clustered, the rest are only labelled
+    pub fn set_sample_size(self, sample_size: Option<usize>) -> Self {
+        CURE { sample_size, .. self }
+    }
+
+    pub fn set_no_partitions(self, no_partitions: usize) -> Self {
+        CURE { no_partitions, .. self }
+    }
+
+    /// Factor by which every partition is pre-clustered
+    pub fn set_reduction(self, reduction: usize) -> Self {
+        CURE { reduction, .. self }
+    }
+}
+
+/// Closest cluster lookup over the indexed representatives and those added since the index was built
+struct ClosestSearch<'a, 'b: 'a> {
+    clusters: &'a [Option<Cluster>],
+    tree: &'a KDTree<'b>,
+    owners: &'a [usize]
+}
+
+impl <'a, 'b> ClosestSearch<'a, 'b> {
+    fn closest(&self, index_c: usize, pending: &[(Point, usize)]) -> (Option<usize>, f64) {
+        let is_other = |owner: usize| owner != index_c && self.clusters[owner].is_some();
+
+        self.clusters[index_c].as_ref().unwrap().representative().iter().fold((None, f64::INFINITY), |best, r| {
+            // Indexed representatives of merged clusters are tombstoned by their owner and skipped by the search.
+            // The kd-tree prunes by the per-axis gap, so it searches by euclidean distance, squared afterwards.
+            let indexed = match self.tree.nearest_where::<Euclidean, _>(r.coordinates(), 1, |index_r| is_other(self.owners[index_r])).first() {
+                Some(&(index_r, distance)) => (Some(self.owners[index_r]), distance * distance),
+                None => (None, f64::INFINITY)
+            };
+
+            pending.iter().filter(|&&(_, owner)| is_other(owner)).map(|&(ref p, owner)| {
+                (Some(owner), SquaredEuclidean::distance(r.coordinates(), p.coordinates()))
+            }).chain(Some(indexed).into_iter()).chain(Some(best).into_iter()).fold((None, f64::INFINITY), |a, b| {
+                if b.1 < a.1 { b } else { a }
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0]], 200, 0.3, 0).data().clone()
+    }
+
+    fn separates_blobs(assignments: &[usize]) -> bool {
+        (0..3).all(|blob| assignments[blob * 200..(blob + 1) * 200].iter().all(|a| *a == assignments[blob * 200])) &&
+            assignments[0] != assignments[200] && assignments[0] != assignments[400] && assignments[200] != assignments[400]
+    }
+
+    #[test]
+    fn can_run_cure() {
+        let points = blobs();
+        let output = CURE::new().run(&points, 3);
+
+        assert_eq!(3, output.clusters().len());
+        assert!(separates_blobs(output.assignments()));
+    }
+
+    #[test]
+    fn can_run_cure_on_partitioned_sample() {
+        let points = blobs();
+        let output = CURE::new().set_sample_size(Some(300)).set_no_partitions(3).run(&points, 3);
+
+        assert_eq!(300, output.clusters().iter().map(|c| c.points().len()).sum::<usize>());
+        assert!(separates_blobs(output.assignments()));
+    }
+
+    #[test]
+    fn can_run_without_partitions() {
+        assert!(CURE::new().set_no_partitions(0).run(&[], 3).clusters().is_empty());
+        assert!(separates_blobs(CURE::new().set_no_partitions(0).run(&blobs(), 3).assignments()));
     }
 
-    clusters.into_vec()
-}
\ No newline at end of file
+    #[test]
+    fn can_cure_elongated_clusters() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..2).flat_map(|bar| {
+            (0..200).map(|_| Point::new(vec![10.0 * rng.next_f64(), 2.0 * bar as f64 + 0.2 * rng.next_f64()])).collect::<Vec<Point>>()
+        }).collect();
+
+        let clusters = cure(points, 2, 10, 0.2);
+
+        assert_eq!(2, clusters.len());
+        for cluster in clusters {
+            assert!(cluster.points().iter().all(|index_p| *index_p / 200 == cluster.points()[0] / 200));
+        }
+    }
+}
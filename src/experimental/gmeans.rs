@@ -0,0 +1,195 @@
+// http://papers.nips.cc/paper/2526-learning-the-k-in-k-means.pdf
+
+use std::f64;
+use std::f64::consts::PI;
+use std::usize;
+use point::Point;
+use statistics::statistics::Statistics;
+use clustering::kmeans::*;
+
+pub struct GMeans {
+    assignments: Vec<usize>,
+    centroids: Vec<Point>,
+    iterations: usize,
+    converged: bool,
+    significance: f64,
+    no_clusters_max: Option<usize>,
+    min_cluster_size: usize,
+    max_iterations: usize
+}
+
+impl Default for GMeans {
+    fn default() -> GMeans {
+        GMeans {
+            assignments: vec![],
+            centroids: vec![],
+            iterations: 0,
+            converged: false,
+            significance: 0.0001,
+            no_clusters_max: None,
+            min_cluster_size: 8,
+            max_iterations: 100
+        }
+    }
+}
+
+impl GMeans {
+    pub fn new() -> Self {
+        GMeans::default()
+    }
+
+    /// Starts from `no_clusters_min` clusters, at least 1, and splits every cluster that does not look gaussian
+    pub fn run(self, points: &[Point], no_clusters_min: usize) -> Self {
+        let no_clusters_min = no_clusters_min.max(1);
+        let no_clusters_max = self.no_clusters_max.unwrap_or(usize::MAX).max(no_clusters_min);
+        let mut kmeans = KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus)
+                                      .set_max_iterations(self.max_iterations)
+                                      .run(points, no_clusters_min);
+
+        let mut i = 1;
+        let mut converged = false;
+
+        while kmeans.centroids().len() < no_clusters_max {
+            let mut centroids: Vec<Vec<f64>> = vec![];
+            let mut budget = no_clusters_max - kmeans.centroids().len();
+
+            for (index_c, centroid) in kmeans.centroids().iter().enumerate() {
+                let cluster: Vec<Point> = points.iter().zip(kmeans.assignments().iter()).filter(|&(_, a)| *a == index_c).map(|(p, _)| p.clone()).collect();
+
+                match self.split(&cluster, centroid.coordinates()) {
+                    Some(children) if budget > 0 => {
+                        centroids.extend(children);
+                        budget -= 1;
+                    },
+                    _ => centroids.push(centroid.coordinates().to_vec())
+                }
+            }
+
+            if centroids.len() == kmeans.centroids().len() {
+                converged = true;
+                break;
+            }
+
+            kmeans = KMeans::new().set_init_method(KMeansInitialization::Precomputed)
+                                  .set_precomputed(&Some(centroids))
+                                  .set_max_iterations(self.max_iterations)
+                                  .run(points, no_clusters_min);
+            i += 1;
+        }
+
+        GMeans {
+            assignments: kmeans.assignments().to_vec(),
+            centroids: kmeans.centroids().to_vec(),
+            iterations: i,
+            converged,
+            .. self
+        }
+    }
+
+    // Two children along the principal component, if the cluster projected onto them fails the normality test
+    fn split(&self, cluster: &[Point], centroid: &[f64]) -> Option<Vec<Vec<f64>>> {
+        if cluster.len() < self.min_cluster_size.max(3) {
+            return None
+        }
+
+        let coordinates: Vec<&[f64]> = cluster.iter().map(|p| p.coordinates()).collect();
+        let (values, vectors) = Statistics::symmetric_eigen(&Statistics::covariance(&coordinates));
+        let offset: Vec<f64> = vectors[0].iter().map(|s| s * (2.0 * values[0].max(0.0) / PI).sqrt()).collect();
+
+        let initial = vec![
+            centroid.iter().zip(offset.iter()).map(|(c, m)| c + m).collect(),
+            centroid.iter().zip(offset.iter()).map(|(c, m)| c - m).collect()
+        ];
+
+        let children = KMeans::new().set_init_method(KMeansInitialization::Precomputed)
+                                    .set_precomputed(&Some(initial))
+                                    .set_max_iterations(self.max_iterations)
+                                    .run(cluster, 2);
+        if children.centroids().len() < 2 {
+            return None
+        }
+
+        let direction: Vec<f64> = children.centroids()[0].coordinates().iter().zip(children.centroids()[1].coordinates().iter()).map(|(a, b)| a - b).collect();
+        let squared_norm = direction.iter().map(|v| v * v).sum::<f64>();
+        if squared_norm == 0.0 {
+            return None
+        }
+
+        let projected: Vec<f64> = coordinates.iter().map(|x| x.iter().zip(direction.iter()).map(|(a, b)| a * b).sum::<f64>() / squared_norm).collect();
+        let mean = projected.iter().sum::<f64>() / projected.len() as f64;
+        if projected.iter().all(|x| (x - mean).abs() < f64::EPSILON) {
+            return None
+        }
+
+        let (_, p_value) = Statistics::anderson_darling(&projected);
+
+        match p_value < self.significance {
+            true => Some(children.centroids().iter().map(|c| c.coordinates().to_vec()).collect()),
+            false => None
+        }
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// The chosen number of clusters
+    pub fn no_clusters(&self) -> usize { self.centroids.len() }
+
+    /// True if the search stopped because every cluster passed the normality test, rather than at `no_clusters_max`
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    /// Significance level of the Anderson-Darling test, lower values splitting less
+    pub fn set_significance(self, significance: f64) -> Self {
+        GMeans { significance, .. self }
+    }
+
+    pub fn set_no_clusters_max(self, no_clusters_max: Option<usize>) -> Self {
+        GMeans { no_clusters_max, .. self }
+    }
+
+    /// Clusters with fewer points are never split
+    pub fn set_min_cluster_size(self, min_cluster_size: usize) -> Self {
+        GMeans { min_cluster_size, .. self }
+    }
+
+    /// Maximum number of iterations of every inner KMeans run
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        GMeans { max_iterations, .. self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0], &[20.0, 0.0]], 200, 0.5, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_gmeans() {
+        let points = blobs();
+        let output = GMeans::new().run(&points, 1);
+
+        assert_eq!(4, output.no_clusters());
+        assert!(output.converged());
+        for blob in 0..4 {
+            assert!(output.assignments()[blob * 200..(blob + 1) * 200].iter().all(|a| *a == output.assignments()[blob * 200]));
+        }
+    }
+
+    #[test]
+    fn starts_from_at_least_one_cluster() {
+        let points = blobs();
+        let output = GMeans::new().run(&points, 0);
+
+        assert!(output.no_clusters() > 0);
+        assert_eq!(points.len(), output.assignments().len());
+    }
+}
@@ -7,33 +7,38 @@ use std::cmp::Ordering;
 #[derive(Eq, PartialEq, Clone, Debug)]
 pub struct KDTree<'a> {
     node: Option<&'a Point>,
+    index: usize,
     left: Option<Box<KDTree<'a>>>,
     right: Option<Box<KDTree<'a>>>,
     axis: usize
 }
 
 impl <'a> KDTree<'a> {
+    /// Builds a balanced tree, each node remembering the position of its point in `points`
     pub fn new(points: &mut [&'a Point]) -> Self {
-        match points.is_empty() {
+        let mut indexed_points: Vec<(usize, &'a Point)> = points.iter().cloned().enumerate().collect();
+
+        match indexed_points.is_empty() {
             true => Self::empty(),
-            false => *Self::new_with_depth(points, 0).unwrap()
+            false => *Self::new_with_depth(indexed_points.as_mut_slice(), 0).unwrap()
         }
     }
 
-    fn new_with_depth(points: &mut [&'a Point], depth: usize) -> Option<Box<Self>> {
+    fn new_with_depth(points: &mut [(usize, &'a Point)], depth: usize) -> Option<Box<Self>> {
         match points.len() {
             0 => None,
             1 => {
-                Self::new_node(points[0], depth % points[0].coordinates().len())
+                Self::new_node(points[0].1, points[0].0, depth % points[0].1.coordinates().len())
             }
             _ => {
-                let axis = depth % points[0].coordinates().len();
+                let axis = depth % points[0].1.coordinates().len();
 
-                points.sort_by(|a, b| a.coordinates()[axis].partial_cmp(&b.coordinates()[axis]).unwrap());
+                points.sort_by(|a, b| a.1.coordinates()[axis].partial_cmp(&b.1.coordinates()[axis]).unwrap());
                 let median = points.len() / 2;
 
                 Some(Box::new(KDTree {
-                    node: Some(&points[median]),
+                    node: Some(points[median].1),
+                    index: points[median].0,
                     left: Self::new_with_depth(&mut points[0..median], depth + 1),
                     right: Self::new_with_depth(&mut points[median + 1..], depth + 1),
                     axis: axis
@@ -45,24 +50,26 @@ impl <'a> KDTree<'a> {
     fn empty() -> Self {
         KDTree {
             node: None,
+            index: 0,
             left: None,
             right: None,
             axis: 0
         }
     }
 
-    fn new_node(point: &'a Point, discriminator: usize) -> Option<Box<Self>> {
+    fn new_node(point: &'a Point, index: usize, discriminator: usize) -> Option<Box<Self>> {
         Some(Box::new(KDTree {
             node: Some(point),
+            index: index,
             left: None,
             right: None,
             axis: discriminator
         }))
     }
 
-    pub fn insert(&mut self, point: &'a mut Point) {
+    pub fn insert(&mut self, point: &'a Point, index: usize) {
         if self.node == None {
-            *self = Self::new(&mut [point]);
+            *self = *Self::new_node(point, index, 0).unwrap();
             return
         }
 
@@ -78,7 +85,7 @@ impl <'a> KDTree<'a> {
                             Some(ref mut right) => nodes.push(right),
                             None => {
                                 let discriminator = (cur_node.axis + 1) % point.coordinates().len();
-                                cur_node.right = Self::new_node(point, discriminator);
+                                cur_node.right = Self::new_node(point, index, discriminator);
                                 return
                             }
                         }
@@ -87,7 +94,7 @@ impl <'a> KDTree<'a> {
                             Some(ref mut left) => nodes.push(left),
                             None => {
                                 let discriminator = (cur_node.axis + 1) % point.coordinates().len();
-                                cur_node.left = Self::new_node(point, discriminator);
+                                cur_node.left = Self::new_node(point, index, discriminator);
                                 return
                             }
                         }
@@ -133,7 +140,7 @@ impl <'a> KDTree<'a> {
         false
     }
 
-    fn recursive_remove(node_to_remove: &'a mut KDTree) {
+    fn recursive_remove(node_to_remove: &mut KDTree) {
         match (node_to_remove.left.as_ref(), node_to_remove.right.as_ref()) {
             (None, None) => {
                 //*node_to_remove = Self::empty();
@@ -170,6 +177,83 @@ impl <'a> KDTree<'a> {
         }*/
     }
 
+    /// Returns every node within `radius` of `point` as (index, distance) pairs, closest first.
+    /// Pruning assumes a metric never smaller than the difference along a single axis.
+    pub fn within<D: Distance>(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let mut neighbors = vec![];
+
+        if self.node.is_some() {
+            Self::within_recursive::<D>(self, point, radius, &mut neighbors);
+        }
+
+        neighbors.sort_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+
+        neighbors
+    }
+
+    fn within_recursive<D: Distance>(cur_node: &KDTree, point: &[f64], radius: f64, neighbors: &mut Vec<(usize, f64)>) {
+        let node = cur_node.node.unwrap().coordinates();
+
+        let distance = D::distance(point, node);
+        if distance <= radius {
+            neighbors.push((cur_node.index, distance));
+        }
+
+        match cur_node.left {
+            Some(ref left) if point[cur_node.axis] - radius <= node[cur_node.axis] => Self::within_recursive::<D>(left, point, radius, neighbors),
+            _ => ()
+        }
+
+        match cur_node.right {
+            Some(ref right) if point[cur_node.axis] + radius >= node[cur_node.axis] => Self::within_recursive::<D>(right, point, radius, neighbors),
+            _ => ()
+        }
+    }
+
+    /// Returns the `k` closest nodes to `point` as (index, distance) pairs, closest first.
+    /// Pruning assumes a metric never smaller than the difference along a single axis.
+    pub fn nearest<D: Distance>(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        self.nearest_where::<D, _>(point, k, |_| true)
+    }
+
+    /// As `nearest`, skipping the nodes whose index fails `keep`, such as points removed since the tree was built
+    pub fn nearest_where<D: Distance, F: Fn(usize) -> bool>(&self, point: &[f64], k: usize, keep: F) -> Vec<(usize, f64)> {
+        let mut neighbors = Vec::with_capacity(k + 1);
+
+        if self.node.is_some() && k > 0 {
+            Self::nearest_recursive::<D, F>(self, point, k, &keep, &mut neighbors);
+        }
+
+        neighbors
+    }
+
+    fn nearest_recursive<D: Distance, F: Fn(usize) -> bool>(cur_node: &KDTree, point: &[f64], k: usize, keep: &F, neighbors: &mut Vec<(usize, f64)>) {
+        let node = cur_node.node.unwrap().coordinates();
+
+        let distance = D::distance(point, node);
+        if keep(cur_node.index) && (neighbors.len() < k || distance < neighbors[neighbors.len() - 1].1) {
+            let position = neighbors.iter().position(|&(_, d)| distance < d).unwrap_or(neighbors.len());
+            neighbors.insert(position, (cur_node.index, distance));
+            neighbors.truncate(k);
+        }
+
+        let difference = point[cur_node.axis] - node[cur_node.axis];
+        let (near, far) = match difference < 0.0 {
+            true => (cur_node.left.as_ref(), cur_node.right.as_ref()),
+            false => (cur_node.right.as_ref(), cur_node.left.as_ref())
+        };
+
+        match near {
+            Some(near) => Self::nearest_recursive::<D, F>(near, point, k, keep, neighbors),
+            None => ()
+        }
+
+        match far {
+            Some(far) if neighbors.len() < k || difference.abs() <= neighbors[neighbors.len() - 1].1 => Self::nearest_recursive::<D, F>(far, point, k, keep, neighbors),
+            _ => ()
+        }
+    }
+
     /*pub fn nearest_neighbor(&self, point: &Point) -> Option<Point> {
         match Self::nearest_neighbor_recursive(self, point, &mut None, f64::INFINITY) {
             Some((p, _)) => Some(p),
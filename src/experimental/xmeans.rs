@@ -1,13 +1,12 @@
-use rand;
-use rand::Rng;
-use rand::distributions::{IndependentSample, Range};
+// http://www.cs.cmu.edu/~dpelleg/download/xmeans.pdf
 
 use std::cmp::Ordering;
+use std::f64;
 use std::usize;
 use point::Point;
-use std::collections::hash_map::Entry::{Occupied, Vacant};
 use experimental::xmeans::XMeansInitialization::*;
 use statistics::distance::{Distance, SquaredEuclidean};
+use statistics::statistics::Statistics;
 use clustering::kmeans::*;
 
 pub struct XMeansDefaults {
@@ -15,14 +14,18 @@ pub struct XMeansDefaults {
     learning_rate: Option<f64>
 }
 
+#[derive(Copy, Clone, Debug)]
 pub enum XMeansInitialization {
     Random,
     KMeansPlusPlus,
     Precomputed
 }
 
+#[derive(Copy, Clone, Debug)]
 pub enum XMeansSplittingCriterion {
+    /// Bayesian information criterion, a split is kept if it raises the score
     BIC,
+    /// Minimum noiseless description length, a split is kept if it lowers the score
     MNDL
 }
 
@@ -30,95 +33,212 @@ pub struct XMeans {
     assignments: Vec<usize>,
     centroids: Vec<Point>,
     iterations: usize,
-    converged: bool
+    converged: bool,
+    no_clusters_max: Option<usize>,
+    init_method: XMeansInitialization,
+    precomputed: Option<Vec<Vec<f64>>>,
+    splitting_criterion: XMeansSplittingCriterion,
+    max_iterations: usize
+}
+
+impl Default for XMeans {
+    fn default() -> XMeans {
+        XMeans {
+            assignments: vec![],
+            centroids: vec![],
+            iterations: 0,
+            converged: false,
+            no_clusters_max: Some(20),
+            init_method: KMeansPlusPlus,
+            precomputed: None,
+            splitting_criterion: XMeansSplittingCriterion::BIC,
+            max_iterations: 100
+        }
+    }
 }
 
 impl XMeans {
-    pub fn run(points: &[Point], no_clusters_min: usize, no_clusters_max: Option<usize>, init_method: XMeansInitialization, precomputed: Option<&[Vec<f64>]>) -> Self {
-        let mut centroids = Self::initial_centroids(points, no_clusters_min, init_method, precomputed);
+    pub fn new() -> Self {
+        XMeans::default()
+    }
+
+    /// Starts from `no_clusters_min` clusters, at least 1, and splits them in two for as long as the criterion improves
+    pub fn run(self, points: &[Point], no_clusters_min: usize) -> Self {
+        if points.is_empty() {
+            return XMeans { assignments: vec![], centroids: vec![], iterations: 0, converged: true, .. self }
+        }
+
+        let no_clusters_min = no_clusters_min.max(1);
+        let no_clusters_max = self.no_clusters_max.unwrap_or(usize::MAX).max(no_clusters_min);
+        let mut centroids = self.initial_centroids(points, no_clusters_min);
 
-        let mut k = no_clusters_min;
+        let mut i = 0;
+        let mut converged = false;
 
-        while no_clusters_max == None || k <= no_clusters_max.unwrap() {
+        let kmeans = loop {
             // 1. Improve-Params
-            let kmeans = KMeans::new();// KMeans::run(points, k, 100, 0.00001, KMeansInitialization::Precomputed, Some(&centroids));
-            let model: Vec<Vec<f64>> = kmeans.centroids().iter().map(|c| c.coordinates().to_vec()).collect();
-            let centroid_distances: Vec<Vec<f64>> = model.iter().map(|m| model.iter().map(|other_m| SquaredEuclidean::distance(m, other_m)).collect()).collect();
+            let kmeans = KMeans::new().set_init_method(KMeansInitialization::Precomputed)
+                                      .set_precomputed(&Some(centroids))
+                                      .set_max_iterations(self.max_iterations)
+                                      .run(points, no_clusters_min);
+            centroids = kmeans.centroids().iter().map(|c| c.coordinates().to_vec()).collect();
+            i += 1;
 
-            for centroid in model.iter() {
+            if centroids.len() >= no_clusters_max {
+                break kmeans;
+            }
+
+            // 2. Improve-Structure, keeping the splits that improve the criterion the most
+            let mut splits: Vec<(f64, usize, Vec<Vec<f64>>)> = centroids.iter().enumerate().filter_map(|(index_c, centroid)| {
+                let cluster: Vec<Point> = points.iter().zip(kmeans.assignments().iter()).filter(|&(_, a)| *a == index_c).map(|(p, _)| p.clone()).collect();
+
+                if cluster.len() < 4 {
+                    return None
+                }
+
+                let children = KMeans::new().set_init_method(KMeansInitialization::KMeansPlusPlus)
+                                            .set_max_iterations(self.max_iterations)
+                                            .run(&cluster, 2);
+                if children.centroids().len() < 2 {
+                    return None
+                }
+
+                let parent_score = self.score(&cluster, &[0; 0], &[centroid.clone()]);
+                let children_centroids: Vec<Vec<f64>> = children.centroids().iter().map(|c| c.coordinates().to_vec()).collect();
+                let children_score = self.score(&cluster, children.assignments(), &children_centroids);
+
+                match children_score > parent_score {
+                    true => Some((children_score - parent_score, index_c, children_centroids)),
+                    false => None
+                }
+            }).collect();
 
+            if splits.is_empty() {
+                converged = true;
+                break kmeans;
             }
 
-            if model.len() == centroids.len() {
-                break;
-            } else {
-                centroids = model;
+            splits.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+            splits.truncate(no_clusters_max - centroids.len());
+
+            let mut updated_centroids: Vec<Vec<f64>> = vec![];
+            for (index_c, centroid) in centroids.into_iter().enumerate() {
+                match splits.iter().position(|&(_, index_s, _)| index_s == index_c) {
+                    Some(index_s) => updated_centroids.extend(splits[index_s].2.iter().cloned()),
+                    None => updated_centroids.push(centroid)
+                }
             }
 
-            k += 1;
-        }
+            centroids = updated_centroids;
+        };
 
         XMeans {
-            assignments: vec![], //previous_round.into_iter().sorted_by(|&(p1, _), &(p2, _)| p1.partial_cmp(&p2).unwrap_or(Ordering::Equal)).into_iter().map(|(_, c)| c).collect(),
+            assignments: kmeans.assignments().to_vec(),
             centroids: centroids.into_iter().map(|c| Point::new(c)).collect(),
-            iterations: k - no_clusters_min,
-            converged: true // TODO
+            iterations: i,
+            converged,
+            .. self
         }
     }
 
-    fn initial_centroids(points: &[Point], no_clusters: usize, init_method: XMeansInitialization, precomputed: Option<&[Vec<f64>]>) -> Vec<Vec<f64>> {
-        match init_method {
-            Random => {
-                let mut rng = rand::thread_rng();
-                let between = Range::new(0, points.len());
+    // Score of the model, higher is better. An empty assignment slice puts every point in the first cluster.
+    fn score(&self, points: &[Point], assignments: &[usize], centroids: &[Vec<f64>]) -> f64 {
+        let cluster_of = |index_p: usize| if assignments.is_empty() { 0 } else { assignments[index_p] };
 
-                (0..no_clusters).map(|_| {
-                    points[between.ind_sample(&mut rng)].coordinates().to_vec()
-                }).collect()
-            },
-            KMeansPlusPlus => {
-                let mut rng = rand::thread_rng();
-                let between = Range::new(0, points.len());
-
-                let mut distances: Vec<f64> = vec![0.0; points.len()];
-                let mut centroids: Vec<Vec<f64>> = vec![points[between.ind_sample(&mut rng)].coordinates().to_vec()];
-
-                for _ in 1..no_clusters {
-                    let mut sum = points.iter().enumerate().fold(0.0, |sum, (index_p, p)| {
-                        let (_, distance_c) = Self::closest_centroid(p.coordinates(), centroids.as_slice());
-                        distances[index_p] = distance_c;
-                        sum + distance_c
-                    });
-
-                    sum *= rng.next_f64();
-                    for (index_p, d) in distances.iter().enumerate() {
-                        sum -= *d;
-
-                        if sum <= 0f64 {
-                            centroids.push(points[index_p].coordinates().to_vec());
-                            break;
-                        }
-                    }
+        match self.splitting_criterion {
+            XMeansSplittingCriterion::BIC => {
+                let mut cluster_size = vec![0.0; centroids.len()];
+                let distortion = points.iter().enumerate().map(|(index_p, p)| {
+                    cluster_size[cluster_of(index_p)] += 1.0;
+                    SquaredEuclidean::distance(p.coordinates(), &centroids[cluster_of(index_p)])
+                }).sum::<f64>();
+
+                let k = centroids.len() as f64;
+                let n = points.len() as f64;
+
+                if n <= k || distortion <= 0.0 {
+                    return f64::NEG_INFINITY
                 }
 
-                centroids
+                Statistics::bic(k, n, points[0].coordinates().len() as f64, distortion, &cluster_size)
             },
-            Precomputed => {
-                precomputed.expect("Expected a slice of clusters, on the form Vec<f64>").to_vec()
-            }
+            XMeansSplittingCriterion::MNDL => -Self::mndl(points, &(0..points.len()).map(cluster_of).collect::<Vec<usize>>(), centroids)
         }
     }
 
-    #[inline]
-    fn closest_centroid(point: &[f64], centroids: &[Vec<f64>]) -> (usize, f64) {
-        match centroids.iter().enumerate().map(|(index_c, c)| {
-            (index_c, SquaredEuclidean::distance(point, c))
-        }).min_by(|&(_, a), &(_, b)| {
-            a.partial_cmp(&b).unwrap_or(Ordering::Equal)
-        }) {
-            Some(closest) => closest,
-            None => panic!()
+    // Minimum noiseless description length of the clustering, lower is better
+    fn mndl(points: &[Point], assignments: &[usize], centroids: &[Vec<f64>]) -> f64 {
+        let alpha: f64 = 0.9;
+        let beta: f64 = 0.9;
+
+        let k = centroids.len() as f64;
+        let n = points.len() as f64;
+        let mut cluster_size = vec![0.0; centroids.len()];
+        let mut distances = vec![0.0; centroids.len()];
+
+        for (p, a) in points.iter().zip(assignments.iter()) {
+            cluster_size[*a] += 1.0;
+            distances[*a] += SquaredEuclidean::distance(p.coordinates(), &centroids[*a]);
         }
+
+        if n <= k || cluster_size.iter().any(|size| *size == 0.0) {
+            return f64::INFINITY
+        }
+
+        let w = distances.iter().zip(cluster_size.iter()).map(|(d, size)| d / size).sum::<f64>();
+        let sigma_squared = distances.iter().sum::<f64>() / (n - k);
+        let sigma = sigma_squared.sqrt();
+
+        let kw = (1.0 - k / n) * sigma_squared;
+        let ks = (2.0 * alpha * sigma / n.sqrt()) * (alpha * alpha * sigma_squared / n + w - kw / 2.0).max(0.0).sqrt();
+
+        sigma_squared * (2.0 * k).sqrt() * ((2.0 * k).sqrt() + beta) / n + w - sigma_squared + ks + 2.0 * alpha.sqrt() * sigma_squared / n
+    }
+
+    fn initial_centroids(&self, points: &[Point], no_clusters: usize) -> Vec<Vec<f64>> {
+        let init_method = match self.init_method {
+            Random => KMeansInitialization::Random,
+            KMeansPlusPlus => KMeansInitialization::KMeansPlusPlus,
+            Precomputed => KMeansInitialization::Precomputed
+        };
+
+        KMeans::new().set_init_method(init_method).set_precomputed(&self.precomputed).initial_centroids(points, no_clusters)
+    }
+
+    pub fn assignments(&self) -> &[usize] { &self.assignments }
+
+    pub fn centroids(&self) -> &[Point] {
+        &self.centroids
+    }
+
+    /// The chosen number of clusters
+    pub fn no_clusters(&self) -> usize { self.centroids.len() }
+
+    /// True if the search stopped because no split improved the criterion, rather than at `no_clusters_max`
+    pub fn converged(&self) -> bool { self.converged }
+
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    /// Upper bound on the number of clusters, or None to split for as long as the criterion improves
+    pub fn set_no_clusters_max(self, no_clusters_max: Option<usize>) -> Self {
+        XMeans { no_clusters_max, .. self }
+    }
+
+    pub fn set_init_method(self, init_method: XMeansInitialization) -> Self {
+        XMeans { init_method, .. self }
+    }
+
+    pub fn set_precomputed(self, precomputed: &Option<Vec<Vec<f64>>>) -> Self {
+        XMeans { precomputed: precomputed.clone(), .. self }
+    }
+
+    pub fn set_splitting_criterion(self, splitting_criterion: XMeansSplittingCriterion) -> Self {
+        XMeans { splitting_criterion, .. self }
+    }
+
+    /// Maximum number of iterations of every inner KMeans run
+    pub fn set_max_iterations(self, max_iterations: usize) -> Self {
+        XMeans { max_iterations, .. self }
     }
 }
 
@@ -128,11 +248,64 @@ mod tests {
     use rand;
     use rand::Rng;
     use time;
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[5.0, 5.0], &[-5.0, 5.0], &[20.0, 0.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_run_xmeans() {
+        let points = blobs();
+        let output = XMeans::new().run(&points, 2);
+
+        assert_eq!(4, output.no_clusters());
+        assert!(output.converged());
+        for blob in 0..4 {
+            assert!(output.assignments()[blob * 100..(blob + 1) * 100].iter().all(|a| *a == output.assignments()[blob * 100]));
+        }
+    }
+
+    #[test]
+    fn can_run_xmeans_with_mndl() {
+        let points: Vec<Point> = blobs().into_iter().take(200).collect();
+        let output = XMeans::new().set_splitting_criterion(XMeansSplittingCriterion::MNDL).run(&points, 1);
+
+        assert_eq!(2, output.no_clusters());
+        assert!(output.assignments()[..100].iter().all(|a| *a == output.assignments()[0]));
+    }
+
+    #[test]
+    fn starts_from_at_least_one_cluster() {
+        let points = blobs();
+        let output = XMeans::new().run(&points, 0);
+
+        assert!(output.no_clusters() > 0);
+        assert_eq!(points.len(), output.assignments().len());
+    }
+
+    #[test]
+    fn can_run_without_points() {
+        for init_method in [Random, KMeansPlusPlus].iter() {
+            let output = XMeans::new().set_init_method(*init_method).run(&[], 2);
+
+            assert_eq!(0, output.no_clusters());
+            assert!(output.assignments().is_empty());
+        }
+    }
+
+    #[test]
+    fn stops_at_no_clusters_max() {
+        let output = XMeans::new().set_no_clusters_max(Some(3)).run(&blobs(), 2);
+
+        assert_eq!(3, output.no_clusters());
+        assert!(!output.converged());
+    }
 
     //#[test]
     fn bench_100000_points() {
         let mut rng = rand::thread_rng();
-        let mut points: Vec<Point> = (0..100000).map(|_| {
+        let points: Vec<Point> = (0..100000).map(|_| {
             Point::new((0..2).into_iter().map(|_| rng.next_f64()).collect())
         }).collect();
 
@@ -140,7 +313,7 @@ mod tests {
         let mut total = 0_u64;
         for _ in 0..repeat_count {
             let start = time::precise_time_ns();
-            XMeans::run(points.as_mut_slice(), 2, Some(10), XMeansInitialization::Random, None);
+            XMeans::new().set_init_method(XMeansInitialization::Random).set_no_clusters_max(Some(10)).run(&points, 2);
             let end = time::precise_time_ns();
             total += end - start
         }
@@ -150,4 +323,4 @@ mod tests {
 
         println!("{} runs, avg {}", repeat_count, avg_ms);
     }
-}
\ No newline at end of file
+}
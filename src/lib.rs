@@ -9,12 +9,24 @@ extern crate rayon;
 extern crate nalgebra;
 
 pub mod clustering {
+    pub mod affinity_propagation;
     pub mod agglomerative;
+    pub mod bisecting_kmeans;
+    pub mod birch;
+    pub mod clara;
+    pub mod clarans;
+    pub mod dbscan;
+    pub mod dendrogram;
     pub mod fuzzy_cmeans;
+    pub mod gaussian_mixture;
+    pub mod hdbscan;
     pub mod kmeans;
     pub mod kmedians;
     pub mod kmedoids;
+    pub mod mean_shift;
     pub mod mini_batch_kmeans;
+    pub mod optics;
+    pub mod spectral;
 }
 
 pub mod statistics {
@@ -26,9 +38,10 @@ pub mod experimental {
     pub mod kdtree;
     pub mod cure;
     pub mod xmeans;
-    pub mod clarans;
+    pub mod gmeans;
     pub mod cluster;
 }
 
 pub mod point;
-pub mod datasets;
\ No newline at end of file
+pub mod datasets;
+pub mod neighbors;
\ No newline at end of file
@@ -0,0 +1,63 @@
+use std::marker::PhantomData;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::{NeighborIndex, sort_by_distance};
+
+pub struct BruteForce<'a, D: Distance> {
+    points: &'a [Point],
+    metric: PhantomData<D>
+}
+
+impl <'a, D: Distance> BruteForce<'a, D> {
+    pub fn new(points: &'a [Point]) -> Self {
+        BruteForce {
+            points: points,
+            metric: PhantomData
+        }
+    }
+}
+
+impl <'a, D: Distance> NeighborIndex for BruteForce<'a, D> {
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.points.iter().enumerate().map(|(index_p, p)| {
+            (index_p, D::distance(point, p.coordinates()))
+        }).collect();
+
+        sort_by_distance(&mut neighbors);
+        neighbors.truncate(k);
+
+        neighbors
+    }
+
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.points.iter().enumerate().map(|(index_p, p)| {
+            (index_p, D::distance(point, p.coordinates()))
+        }).filter(|&(_, distance)| distance <= radius).collect();
+
+        sort_by_distance(&mut neighbors);
+
+        neighbors
+    }
+
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statistics::distance::Euclidean;
+
+    #[test]
+    fn can_find_nearest_and_within() {
+        let points = vec![Point::new(vec![0.0, 0.0]), Point::new(vec![1.0, 0.0]), Point::new(vec![5.0, 5.0])];
+        let index: BruteForce<Euclidean> = BruteForce::new(&points);
+
+        let nearest = index.nearest(&[0.9, 0.0], 2);
+        assert_eq!(vec![1, 0], nearest.iter().map(|&(i, _)| i).collect::<Vec<usize>>());
+
+        let within = index.within(&[0.0, 0.0], 1.5);
+        assert_eq!(vec![0, 1], within.iter().map(|&(i, _)| i).collect::<Vec<usize>>());
+    }
+}
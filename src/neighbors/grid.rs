@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::{NeighborIndex, sort_by_distance};
+use rayon::prelude::*;
+
+/// Uniform grid over the coordinate space, intended for low-dimensional data.
+/// Pruning assumes a metric that is never smaller than the largest per-axis
+/// difference (e.g. `Euclidean`, `Manhattan`, `Chebyshev`), other metrics panic on `build`.
+pub struct Grid<'a, D: Distance> {
+    points: &'a [Point],
+    cells: HashMap<Vec<i64>, Vec<usize>>,
+    cell_size: f64,
+    metric: PhantomData<D>
+}
+
+impl <'a, D: Distance> Default for Grid<'a, D> {
+    fn default() -> Grid<'a, D> {
+        Grid {
+            points: &[],
+            cells: HashMap::new(),
+            cell_size: 1.0,
+            metric: PhantomData
+        }
+    }
+}
+
+impl <'a, D: Distance> Grid<'a, D> {
+    pub fn new() -> Self {
+        Grid::default()
+    }
+
+    pub fn build(self, points: &'a [Point]) -> Self {
+        if !D::bounds_axis_gap() {
+            panic!("Expected a metric bounded by the per-axis gap for a grid, use brute force or HNSW search instead");
+        }
+
+        let cell_size = self.cell_size;
+
+        let cells = points.par_iter().enumerate().fold(|| HashMap::new(), |mut cells, (index_p, p)| {
+            (*cells.entry(Self::cell(p.coordinates(), cell_size)).or_insert(vec![])).push(index_p);
+            cells
+        }).reduce(|| HashMap::new(), |mut cells, partial| {
+            for (k, v) in partial.into_iter() {
+                (*cells.entry(k).or_insert(vec![])).extend(v);
+            }
+
+            cells
+        });
+
+        Grid {
+            points,
+            cells,
+            .. self
+        }
+    }
+
+    /// Returns the indexes of the points in every cell whose offset from the cell of `point`
+    /// is at least `min_offset` and at most `max_offset` along the largest axis
+    fn points_in_shell(&self, point: &[f64], min_offset: i64, max_offset: i64) -> Vec<usize> {
+        let center = Self::cell(point, self.cell_size);
+        let volume = (2.0 * max_offset as f64 + 1.0).powi(center.len() as i32);
+
+        if volume > self.cells.len() as f64 {
+            return self.cells.iter().filter(|&(cell, _)| {
+                let offset = Self::offset(cell, &center);
+                offset >= min_offset && offset <= max_offset
+            }).flat_map(|(_, points)| points.iter().cloned()).collect()
+        }
+
+        let mut neighbor_cells: Vec<Vec<i64>> = vec![vec![]];
+        for axis in 0..center.len() {
+            neighbor_cells = neighbor_cells.into_iter().flat_map(|cell| {
+                (center[axis] - max_offset..center[axis] + max_offset + 1).map(move |c| {
+                    let mut neighbor = cell.clone();
+                    neighbor.push(c);
+                    neighbor
+                })
+            }).collect();
+        }
+
+        neighbor_cells.into_iter().filter(|cell| Self::offset(cell, &center) >= min_offset).flat_map(|cell| {
+            match self.cells.get(&cell) {
+                Some(points) => points.clone(),
+                None => vec![]
+            }
+        }).collect()
+    }
+
+    #[inline]
+    fn cell(point: &[f64], cell_size: f64) -> Vec<i64> {
+        point.iter().map(|x| (x / cell_size).floor() as i64).collect()
+    }
+
+    #[inline]
+    fn offset(a: &[i64], b: &[i64]) -> i64 {
+        a.iter().zip(b.iter()).map(|(x, y)| (x - y).abs()).max().unwrap_or(0)
+    }
+
+    pub fn cell_size(&self) -> f64 { self.cell_size }
+
+    pub fn set_cell_size(self, cell_size: f64) -> Self {
+        Grid { cell_size, .. self }
+    }
+}
+
+impl <'a, D: Distance> NeighborIndex for Grid<'a, D> {
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        let k = k.min(self.points.len());
+        if k == 0 {
+            return vec![]
+        }
+
+        let mut neighbors: Vec<(usize, f64)> = vec![];
+        let mut ring = 0;
+
+        // Every point outside the rings visited so far is at least ring * cell_size away
+        while (2.0 * ring as f64 + 1.0).powi(point.len() as i32) <= self.cells.len() as f64 {
+            neighbors.extend(self.points_in_shell(point, ring, ring).into_iter().map(|index_p| {
+                (index_p, D::distance(point, self.points[index_p].coordinates()))
+            }));
+
+            sort_by_distance(&mut neighbors);
+
+            if neighbors.len() == self.points.len() || (neighbors.len() >= k && neighbors[k - 1].1 <= ring as f64 * self.cell_size) {
+                neighbors.truncate(k);
+                return neighbors
+            }
+
+            ring += 1;
+        }
+
+        // Rings larger than the number of occupied cells are mostly empty, so the occupied cells are visited by offset instead
+        let center = Self::cell(point, self.cell_size);
+        let mut remaining: Vec<(i64, &Vec<usize>)> = self.cells.iter().map(|(cell, points)| (Self::offset(cell, &center), points))
+                                                                 .filter(|&(offset, _)| offset >= ring)
+                                                                 .collect();
+        remaining.sort_by_key(|&(offset, _)| offset);
+
+        for (index_r, &(offset, points)) in remaining.iter().enumerate() {
+            neighbors.extend(points.iter().map(|index_p| (*index_p, D::distance(point, self.points[*index_p].coordinates()))));
+
+            // Points in cells at the next offset are at least (next - 1) * cell_size away
+            match remaining.get(index_r + 1) {
+                Some(&(next, _)) if next > offset => {
+                    sort_by_distance(&mut neighbors);
+
+                    if neighbors.len() >= k && neighbors[k - 1].1 <= (next - 1) as f64 * self.cell_size {
+                        break;
+                    }
+                },
+                _ => ()
+            }
+        }
+
+        sort_by_distance(&mut neighbors);
+        neighbors.truncate(k);
+
+        neighbors
+    }
+
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let max_offset = (radius / self.cell_size).ceil() as i64;
+
+        let mut neighbors: Vec<(usize, f64)> = self.points_in_shell(point, 0, max_offset).into_iter().map(|index_p| {
+            (index_p, D::distance(point, self.points[index_p].coordinates()))
+        }).filter(|&(_, distance)| distance <= radius).collect();
+
+        sort_by_distance(&mut neighbors);
+
+        neighbors
+    }
+
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use statistics::distance::Euclidean;
+    use neighbors::brute_force::BruteForce;
+
+    #[test]
+    fn grid_matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..1000).map(|_| {
+            Point::new((0..2).into_iter().map(|_| 10.0 * rng.next_f64()).collect())
+        }).collect();
+
+        let index: Grid<Euclidean> = Grid::new().set_cell_size(0.5).build(&points);
+        let exact: BruteForce<Euclidean> = BruteForce::new(&points);
+
+        for p in points.iter().take(50) {
+            let expected: Vec<usize> = exact.within(p.coordinates(), 0.5).into_iter().map(|(i, _)| i).collect();
+            let found: Vec<usize> = index.within(p.coordinates(), 0.5).into_iter().map(|(i, _)| i).collect();
+            assert_eq!(expected, found);
+
+            let expected: Vec<usize> = exact.nearest(p.coordinates(), 7).into_iter().map(|(i, _)| i).collect();
+            let found: Vec<usize> = index.nearest(p.coordinates(), 7).into_iter().map(|(i, _)| i).collect();
+            assert_eq!(expected, found);
+        }
+
+        assert!(index.nearest(points[0].coordinates(), 0).is_empty());
+    }
+
+    #[test]
+    fn can_find_nearest_far_from_points() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..1000).map(|_| {
+            Point::new((0..2).into_iter().map(|_| rng.next_f64()).collect())
+        }).collect();
+
+        let index: Grid<Euclidean> = Grid::new().set_cell_size(0.001).build(&points);
+        let exact: BruteForce<Euclidean> = BruteForce::new(&points);
+
+        for query in [[1000.0, 1000.0], [-50.0, 0.5], [0.5, 0.5]].iter() {
+            let expected: Vec<usize> = exact.nearest(query, 5).into_iter().map(|(i, _)| i).collect();
+            let found: Vec<usize> = index.nearest(query, 5).into_iter().map(|(i, _)| i).collect();
+            assert_eq!(expected, found);
+        }
+    }
+}
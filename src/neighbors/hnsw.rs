@@ -0,0 +1,306 @@
+// https://arxiv.org/pdf/1603.09320.pdf
+
+use rand;
+use rand::Rng;
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::marker::PhantomData;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::{NeighborIndex, sort_by_distance};
+
+#[derive(Copy, Clone, Debug)]
+struct Candidate {
+    distance: f64,
+    index: usize
+}
+
+impl Eq for Candidate {}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.distance == other.distance && self.index == other.index
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.distance.partial_cmp(&other.distance)
+    }
+}
+
+/// Approximate neighbour graph over `D::distance`. The greedy search assumes a dissimilarity, smaller meaning closer,
+/// so `CosineSimilarity` needs `CosineDistance` instead, and `Minkowski` and `Mahalanobis` panic on insertion
+pub struct HNSW<D: Distance> {
+    points: Vec<Point>,
+    links: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    max_connections: usize,
+    ef_construction: usize,
+    ef: usize,
+    metric: PhantomData<D>
+}
+
+impl <D: Distance> Default for HNSW<D> {
+    fn default() -> HNSW<D> {
+        HNSW {
+            points: vec![],
+            links: vec![],
+            entry_point: None,
+            max_level: 0,
+            max_connections: 16,
+            ef_construction: 200,
+            ef: 50,
+            metric: PhantomData
+        }
+    }
+}
+
+impl <D: Distance> HNSW<D> {
+    pub fn new() -> Self {
+        HNSW::default()
+    }
+
+    pub fn build(mut self, points: &[Point]) -> Self {
+        self.points.reserve(points.len());
+        self.links.reserve(points.len());
+
+        for point in points.iter() {
+            self.insert(point.clone());
+        }
+
+        self
+    }
+
+    pub fn insert(&mut self, point: Point) -> usize {
+        let mut rng = rand::thread_rng();
+        let level_multiplier = 1.0 / (self.max_connections.max(2) as f64).ln();
+        let level = (-(1.0 - rng.next_f64()).ln() * level_multiplier).floor() as usize;
+
+        let index = self.points.len();
+        self.points.push(point);
+        self.links.push(vec![vec![]; level + 1]);
+
+        let mut entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.entry_point = Some(index);
+                self.max_level = level;
+                return index
+            }
+        };
+
+        let query = self.points[index].coordinates().to_vec();
+
+        let mut layer = self.max_level;
+        while layer > level {
+            entry_point = self.search_layer(&query, &[entry_point], 1, layer)[0].index;
+            layer -= 1;
+        }
+
+        let mut entry_points = vec![entry_point];
+        for layer in (0..level.min(self.max_level) + 1).rev() {
+            let candidates = self.search_layer(&query, &entry_points, self.ef_construction, layer);
+            let max_connections = self.max_connections(layer);
+
+            for neighbor in candidates.iter().take(self.max_connections) {
+                self.links[index][layer].push(neighbor.index);
+                self.links[neighbor.index][layer].push(index);
+
+                if self.links[neighbor.index][layer].len() > max_connections {
+                    self.shrink_links(neighbor.index, layer, max_connections);
+                }
+            }
+
+            entry_points = candidates.into_iter().map(|c| c.index).collect();
+        }
+
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(index);
+        }
+
+        index
+    }
+
+    pub fn nearest_with_ef(&self, point: &[f64], k: usize, ef: usize) -> Vec<(usize, f64)> {
+        let mut entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => return vec![]
+        };
+
+        for layer in (1..self.max_level + 1).rev() {
+            entry_point = self.search_layer(point, &[entry_point], 1, layer)[0].index;
+        }
+
+        self.search_layer(point, &[entry_point], ef.max(k), 0).into_iter().take(k).map(|c| (c.index, c.distance)).collect()
+    }
+
+    // Greedy best-first search restricted to a single layer, returning up to `ef` candidates closest first
+    fn search_layer(&self, query: &[f64], entry_points: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().cloned().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut results: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &index in entry_points.iter() {
+            let candidate = Candidate { distance: D::distance(query, self.points[index].coordinates()), index: index };
+            candidates.push(Reverse(candidate));
+            results.push(candidate);
+        }
+
+        while results.len() > ef {
+            results.pop();
+        }
+
+        while let Some(Reverse(closest)) = candidates.pop() {
+            match results.peek() {
+                Some(furthest) if closest.distance > furthest.distance && results.len() >= ef => break,
+                _ => ()
+            }
+
+            for &neighbor in self.links[closest.index][layer].iter() {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let candidate = Candidate { distance: D::distance(query, self.points[neighbor].coordinates()), index: neighbor };
+                let admit = match results.peek() {
+                    Some(furthest) => results.len() < ef || candidate.distance < furthest.distance,
+                    None => true
+                };
+
+                if admit {
+                    candidates.push(Reverse(candidate));
+                    results.push(candidate);
+
+                    if results.len() > ef {
+                        results.pop();
+                    }
+                }
+            }
+        }
+
+        results.into_sorted_vec()
+    }
+
+    fn shrink_links(&mut self, index: usize, layer: usize, max_connections: usize) {
+        let mut neighbors: Vec<(usize, f64)> = self.links[index][layer].iter().map(|&neighbor| {
+            (neighbor, D::distance(self.points[index].coordinates(), self.points[neighbor].coordinates()))
+        }).collect();
+
+        sort_by_distance(&mut neighbors);
+        neighbors.truncate(max_connections);
+
+        self.links[index][layer] = neighbors.into_iter().map(|(neighbor, _)| neighbor).collect();
+    }
+
+    #[inline]
+    fn max_connections(&self, layer: usize) -> usize {
+        match layer {
+            0 => 2 * self.max_connections,
+            _ => self.max_connections
+        }
+    }
+
+    pub fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    pub fn ef(&self) -> usize { self.ef }
+
+    pub fn set_max_connections(self, max_connections: usize) -> Self {
+        HNSW { max_connections, .. self }
+    }
+
+    pub fn set_ef_construction(self, ef_construction: usize) -> Self {
+        HNSW { ef_construction, .. self }
+    }
+
+    pub fn set_ef(self, ef: usize) -> Self {
+        HNSW { ef, .. self }
+    }
+}
+
+impl <D: Distance> NeighborIndex for HNSW<D> {
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        self.nearest_with_ef(point, k, self.ef)
+    }
+
+    // Approximate range search: seed with the ef closest points, then walk the bottom layer
+    // through every neighbour that still lies inside the radius.
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.nearest_with_ef(point, self.ef, self.ef).into_iter().filter(|&(_, distance)| distance <= radius).collect();
+        let mut visited: HashSet<usize> = neighbors.iter().map(|&(index, _)| index).collect();
+        let mut stack: Vec<usize> = visited.iter().cloned().collect();
+
+        while let Some(index) = stack.pop() {
+            for &neighbor in self.links[index][0].iter() {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+
+                let distance = D::distance(point, self.points[neighbor].coordinates());
+                if distance <= radius {
+                    neighbors.push((neighbor, distance));
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        sort_by_distance(&mut neighbors);
+
+        neighbors
+    }
+
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use statistics::distance::Euclidean;
+    use neighbors::brute_force::BruteForce;
+
+    #[test]
+    fn hnsw_recall_is_high() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..2000).map(|_| {
+            Point::new((0..8).into_iter().map(|_| rng.next_f64()).collect())
+        }).collect();
+
+        let index: HNSW<Euclidean> = HNSW::new().set_ef(100).build(&points);
+        let exact: BruteForce<Euclidean> = BruteForce::new(&points);
+
+        let k = 10;
+        let hits: usize = points.iter().take(100).map(|p| {
+            let expected: HashSet<usize> = exact.nearest(p.coordinates(), k).into_iter().map(|(i, _)| i).collect();
+            index.nearest(p.coordinates(), k).into_iter().filter(|&(i, _)| expected.contains(&i)).count()
+        }).sum();
+
+        assert_eq!(2000, index.len());
+        assert!(hits as f64 / (100 * k) as f64 > 0.9);
+    }
+
+    #[test]
+    fn hnsw_within_returns_points_inside_radius() {
+        let points: Vec<Point> = (0..100).map(|i| Point::new(vec![i as f64, 0.0])).collect();
+        let index: HNSW<Euclidean> = HNSW::new().build(&points);
+
+        let mut within: Vec<usize> = index.within(&[50.0, 0.0], 2.5).into_iter().map(|(i, _)| i).collect();
+        within.sort();
+
+        assert_eq!(vec![48, 49, 50, 51, 52], within);
+    }
+}
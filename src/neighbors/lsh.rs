@@ -0,0 +1,312 @@
+// http://www.cs.princeton.edu/courses/archive/spring04/cos598B/bib/CharikarEstim.pdf
+// http://www.cs.princeton.edu/courses/archive/spring05/cos598E/bib/p253-datar.pdf
+
+use rand;
+use rand::Rng;
+use rand::distributions::{IndependentSample, Normal, Range};
+
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use point::Point;
+use statistics::distance::Distance;
+use neighbors::{NeighborIndex, assign_nearest, sort_by_distance};
+use neighbors::brute_force::BruteForce;
+use neighbors::lsh::LSHFamily::*;
+use clustering::kmeans::KMeans;
+use rayon::prelude::*;
+
+#[derive(Copy, Clone, Debug)]
+pub enum LSHFamily {
+    /// Sign of random hyperplane projections, approximating cosine distance
+    SignedRandomProjection,
+    /// Quantized gaussian projections with the given bucket width, approximating euclidean distance
+    PStable(f64)
+}
+
+struct HashTable {
+    projections: Vec<Vec<f64>>,
+    offsets: Vec<f64>,
+    buckets: HashMap<Vec<i64>, Vec<usize>>
+}
+
+pub struct LSH<'a, D: Distance> {
+    points: &'a [Point],
+    tables: Vec<HashTable>,
+    family: LSHFamily,
+    no_tables: usize,
+    no_hashes: usize,
+    metric: PhantomData<D>
+}
+
+impl <'a, D: Distance> Default for LSH<'a, D> {
+    fn default() -> LSH<'a, D> {
+        LSH {
+            points: &[],
+            tables: vec![],
+            family: SignedRandomProjection,
+            no_tables: 8,
+            no_hashes: 12,
+            metric: PhantomData
+        }
+    }
+}
+
+impl <'a, D: Distance> LSH<'a, D> {
+    pub fn new() -> Self {
+        LSH::default()
+    }
+
+    pub fn build(self, points: &'a [Point]) -> Self {
+        let dimension = match points.first() {
+            Some(p) => p.coordinates().len(),
+            None => return LSH { points, .. self }
+        };
+
+        let mut rng = rand::thread_rng();
+        let normal = Normal::new(0.0, 1.0);
+
+        let tables = (0..self.no_tables).map(|_| {
+            let projections: Vec<Vec<f64>> = (0..self.no_hashes).map(|_| {
+                (0..dimension).map(|_| normal.ind_sample(&mut rng)).collect()
+            }).collect();
+
+            let offsets: Vec<f64> = match self.family {
+                SignedRandomProjection => vec![0.0; self.no_hashes],
+                PStable(width) => {
+                    let between = Range::new(0.0, width);
+                    (0..self.no_hashes).map(|_| between.ind_sample(&mut rng)).collect()
+                }
+            };
+
+            HashTable {
+                projections,
+                offsets,
+                buckets: HashMap::new()
+            }
+        }).collect::<Vec<HashTable>>();
+
+        let family = self.family;
+        let tables = tables.into_par_iter().map(|mut table| {
+            let keys: Vec<Vec<i64>> = points.par_iter().map(|p| Self::hash(&table, family, p.coordinates())).collect();
+
+            for (index_p, key) in keys.into_iter().enumerate() {
+                (*table.buckets.entry(key).or_insert(vec![])).push(index_p);
+            }
+
+            table
+        }).collect();
+
+        LSH {
+            points,
+            tables,
+            .. self
+        }
+    }
+
+    /// Returns the union of the buckets the point hashes to across all tables
+    pub fn candidates(&self, point: &[f64]) -> HashSet<usize> {
+        self.tables.iter().fold(HashSet::new(), |mut candidates, table| {
+            match table.buckets.get(&Self::hash(table, self.family, point)) {
+                Some(bucket) => candidates.extend(bucket.iter().cloned()),
+                None => ()
+            }
+
+            candidates
+        })
+    }
+
+    /// Returns the buckets of the first table, partitioning the indexed points
+    pub fn buckets(&self) -> Vec<Vec<usize>> {
+        match self.tables.first() {
+            Some(table) => {
+                let mut buckets: Vec<Vec<usize>> = table.buckets.values().cloned().collect();
+                buckets.sort_by(|a, b| a[0].cmp(&b[0]));
+                buckets
+            },
+            None => vec![]
+        }
+    }
+
+    /// Uses the buckets as a canopy pre-pass and runs KMeans inside each of them.
+    /// The `no_clusters` clusters are shared between the buckets in proportion to their size, largest remainders first,
+    /// and the points of buckets left without a cluster are assigned to the closest centroid of the other buckets.
+    /// Returns the assignments of all indexed points together with the combined centroids.
+    pub fn kmeans_per_bucket(&self, no_clusters: usize) -> (Vec<usize>, Vec<Point>) {
+        let points = self.points;
+        let no_clusters = no_clusters.min(points.len());
+        if no_clusters == 0 {
+            return (vec![], vec![])
+        }
+
+        let buckets = self.buckets();
+        let shares = Self::apportion(&buckets, no_clusters);
+
+        let outputs: Vec<Option<KMeans>> = buckets.par_iter().zip(shares.par_iter()).map(|(bucket, &share)| {
+            match share > 0 {
+                true => {
+                    let bucket_points: Vec<Point> = bucket.iter().map(|index_p| points[*index_p].clone()).collect();
+                    Some(KMeans::new().run(&bucket_points, share))
+                },
+                false => None
+            }
+        }).collect();
+
+        let mut assignments = vec![0; points.len()];
+        let mut centroids = vec![];
+
+        for (bucket, output) in buckets.iter().zip(outputs.iter()) {
+            if let Some(ref output) = *output {
+                let offset = centroids.len();
+
+                for (index_p, index_c) in bucket.iter().zip(output.assignments().iter()) {
+                    assignments[*index_p] = offset + index_c;
+                }
+
+                centroids.extend(output.centroids().iter().cloned());
+            }
+        }
+
+        let index = BruteForce::<D>::new(&centroids);
+        for (bucket, output) in buckets.iter().zip(outputs.iter()) {
+            if output.is_none() {
+                let bucket_points: Vec<Point> = bucket.iter().map(|index_p| points[*index_p].clone()).collect();
+
+                for (index_p, index_c) in bucket.iter().zip(assign_nearest(&bucket_points, &index).into_iter()) {
+                    assignments[*index_p] = index_c;
+                }
+            }
+        }
+
+        (assignments, centroids)
+    }
+
+    /// Splits `no_clusters` between the buckets in proportion to their size, by largest remainder
+    fn apportion(buckets: &[Vec<usize>], no_clusters: usize) -> Vec<usize> {
+        let no_points = buckets.iter().map(|bucket| bucket.len()).sum::<usize>();
+        let mut shares: Vec<usize> = buckets.iter().map(|bucket| no_clusters * bucket.len() / no_points).collect();
+
+        let mut remainders: Vec<(usize, usize)> = buckets.iter().enumerate().map(|(index_b, bucket)| {
+            (index_b, no_clusters * bucket.len() % no_points)
+        }).collect();
+        remainders.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let left = no_clusters - shares.iter().sum::<usize>();
+        for &(index_b, _) in remainders.iter().take(left) {
+            shares[index_b] += 1;
+        }
+
+        shares
+    }
+
+    #[inline]
+    fn hash(table: &HashTable, family: LSHFamily, point: &[f64]) -> Vec<i64> {
+        table.projections.iter().zip(table.offsets.iter()).map(|(projection, offset)| {
+            let dot_product = projection.iter().zip(point.iter()).map(|(r, x)| r * x).sum::<f64>();
+
+            match family {
+                SignedRandomProjection => if dot_product >= 0.0 { 1 } else { 0 },
+                PStable(width) => ((dot_product + offset) / width).floor() as i64
+            }
+        }).collect()
+    }
+
+    pub fn set_family(self, family: LSHFamily) -> Self {
+        LSH { family, .. self }
+    }
+
+    pub fn set_no_tables(self, no_tables: usize) -> Self {
+        LSH { no_tables, .. self }
+    }
+
+    pub fn set_no_hashes(self, no_hashes: usize) -> Self {
+        LSH { no_hashes, .. self }
+    }
+}
+
+impl <'a, D: Distance> NeighborIndex for LSH<'a, D> {
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.candidates(point).into_iter().map(|index_p| {
+            (index_p, D::distance(point, self.points[index_p].coordinates()))
+        }).collect();
+
+        sort_by_distance(&mut neighbors);
+        neighbors.truncate(k);
+
+        neighbors
+    }
+
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        let mut neighbors: Vec<(usize, f64)> = self.candidates(point).into_iter().map(|index_p| {
+            (index_p, D::distance(point, self.points[index_p].coordinates()))
+        }).filter(|&(_, distance)| distance <= radius).collect();
+
+        sort_by_distance(&mut neighbors);
+
+        neighbors
+    }
+
+    fn len(&self) -> usize {
+        self.points.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use statistics::distance::{CosineDistance, Euclidean};
+    use datasets::blobs::make_blobs;
+
+    fn blobs() -> Vec<Point> {
+        make_blobs(&[&[0.0, 0.0], &[10.0, 10.0], &[-10.0, 10.0]], 100, 0.3, 0).data().clone()
+    }
+
+    #[test]
+    fn can_find_neighbors_with_pstable_hashing() {
+        let points = blobs();
+        let index: LSH<Euclidean> = LSH::new().set_family(PStable(4.0)).set_no_hashes(2).build(&points);
+
+        let nearest = index.nearest(points[0].coordinates(), 5);
+        assert_eq!(5, nearest.len());
+        assert_eq!(0, nearest[0].0);
+        assert!(nearest.iter().all(|&(i, _)| i < 100));
+    }
+
+    #[test]
+    fn can_find_neighbors_with_signed_projections() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = [0.0, 2.0, 4.0].iter().flat_map(|&angle| {
+            (0..100).map(|_| {
+                let theta: f64 = angle + 0.1 * rng.next_f64();
+                Point::new(vec![theta.cos(), theta.sin()])
+            }).collect::<Vec<Point>>()
+        }).collect();
+
+        let index: LSH<CosineDistance> = LSH::new().set_no_hashes(4).build(&points);
+
+        let within = index.within(points[150].coordinates(), 0.01);
+        assert!(within.iter().any(|&(i, _)| i == 150));
+        assert!(within.iter().all(|&(i, _)| i >= 100 && i < 200));
+    }
+
+    #[test]
+    fn can_run_kmeans_per_bucket() {
+        let points = blobs();
+        let index: LSH<Euclidean> = LSH::new().set_family(PStable(8.0)).set_no_hashes(2).build(&points);
+
+        let (assignments, centroids) = index.kmeans_per_bucket(3);
+        assert_eq!(3, centroids.len());
+        assert_eq!(points.len(), assignments.len());
+        assert!(assignments.iter().all(|a| *a < centroids.len()));
+    }
+
+    #[test]
+    fn kmeans_per_bucket_keeps_number_of_clusters() {
+        let points = blobs();
+        let index: LSH<Euclidean> = LSH::new().set_family(PStable(0.5)).set_no_hashes(4).build(&points);
+        assert!(index.buckets().len() > 3);
+
+        let (assignments, centroids) = index.kmeans_per_bucket(3);
+        assert_eq!(3, centroids.len());
+        assert!(assignments.iter().all(|a| *a < 3));
+    }
+}
@@ -0,0 +1,150 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use point::Point;
+use statistics::distance::Distance;
+use experimental::kdtree::KDTree;
+use neighbors::brute_force::BruteForce;
+use neighbors::grid::Grid;
+use neighbors::hnsw::HNSW;
+use rayon::prelude::*;
+
+/// Module for exhaustive neighbour search.
+pub mod brute_force;
+/// Module for uniform grids.
+pub mod grid;
+/// Module for hierarchical navigable small world graphs.
+pub mod hnsw;
+/// Module for locality-sensitive hashing.
+pub mod lsh;
+
+/// Neighbour search over a fixed set of indexed points
+pub trait NeighborIndex {
+    /// Returns the `k` closest indexed points as (index, distance) pairs, closest first
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)>;
+
+    /// Returns every indexed point within `radius` as (index, distance) pairs, closest first
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)>;
+
+    /// Returns the number of indexed points
+    fn len(&self) -> usize;
+}
+
+/// Neighbour search backend used by the density-based algorithms.
+/// Every backend ranks points by `D::distance`, so `D` must be a dissimilarity, smaller meaning closer:
+/// `CosineSimilarity` ranks the farthest points first, use `CosineDistance` instead. `Minkowski` and `Mahalanobis`
+/// only implement the parameterized distances and panic when searched
+#[derive(Copy, Clone, Debug)]
+pub enum NeighborSearch {
+    BruteForce,
+    /// Exact search pruned by per-axis gaps, only valid for metrics such as
+    /// `Euclidean`, `Manhattan` or `Chebyshev` (see `Distance::bounds_axis_gap`)
+    KDTree,
+    /// Uniform grid with the query radius as cell size, best suited to 2D/3D data,
+    /// with the same metric restriction as `KDTree`
+    Grid,
+    /// Approximate search through a HNSW graph with default parameters
+    HNSW
+}
+
+impl NeighborSearch {
+    /// Builds the chosen index over `points`, tuned for queries of the given radius.
+    /// Panics for `KDTree` and `Grid` when `D` is not bounded by the per-axis gap.
+    pub fn build<'a, D: Distance + 'a>(&self, points: &'a [Point], radius: f64) -> Box<dyn NeighborIndex + Sync + 'a> {
+        match *self {
+            NeighborSearch::KDTree | NeighborSearch::Grid if !D::bounds_axis_gap() => {
+                panic!("Expected a metric bounded by the per-axis gap for {:?} search, use BruteForce or HNSW instead", self);
+            },
+            NeighborSearch::BruteForce => Box::new(BruteForce::<D>::new(points)),
+            NeighborSearch::KDTree => {
+                let mut references: Vec<&Point> = points.iter().collect();
+
+                Box::new(KDTreeIndex::<D> {
+                    tree: KDTree::new(references.as_mut_slice()),
+                    len: points.len(),
+                    metric: PhantomData
+                })
+            },
+            NeighborSearch::Grid => {
+                let cell_size = match radius > 0.0 && radius.is_finite() {
+                    true => radius,
+                    false => 1.0
+                };
+
+                Box::new(Grid::<D>::new().set_cell_size(cell_size).build(points))
+            },
+            NeighborSearch::HNSW => Box::new(HNSW::<D>::new().build(points))
+        }
+    }
+}
+
+struct KDTreeIndex<'a, D: Distance> {
+    tree: KDTree<'a>,
+    len: usize,
+    metric: PhantomData<D>
+}
+
+impl <'a, D: Distance> NeighborIndex for KDTreeIndex<'a, D> {
+    fn nearest(&self, point: &[f64], k: usize) -> Vec<(usize, f64)> {
+        self.tree.nearest::<D>(point, k)
+    }
+
+    fn within(&self, point: &[f64], radius: f64) -> Vec<(usize, f64)> {
+        self.tree.within::<D>(point, radius)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// Returns the index of the closest indexed point for every point, panicking on an empty index
+pub fn assign_nearest<I: NeighborIndex + Sync + ?Sized>(points: &[Point], index: &I) -> Vec<usize> {
+    points.par_iter().map(|p| {
+        index.nearest(p.coordinates(), 1).first().map(|&(index_c, _)| index_c).expect("Expected a non-empty index, found an empty index")
+    }).collect()
+}
+
+#[inline]
+fn sort_by_distance(neighbors: &mut Vec<(usize, f64)>) {
+    neighbors.sort_by(|&(_, a), &(_, b)| a.partial_cmp(&b).unwrap_or(Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand;
+    use rand::Rng;
+    use statistics::distance::{Euclidean, SquaredEuclidean};
+
+    #[test]
+    fn kdtree_index_matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        let points: Vec<Point> = (0..500).map(|_| {
+            Point::new((0..3).into_iter().map(|_| rng.next_f64()).collect())
+        }).collect();
+
+        let index = NeighborSearch::KDTree.build::<Euclidean>(&points, 0.1);
+        let exact = NeighborSearch::BruteForce.build::<Euclidean>(&points, 0.1);
+
+        for p in points.iter().take(50) {
+            assert_eq!(exact.nearest(p.coordinates(), 5), index.nearest(p.coordinates(), 5));
+            assert_eq!(exact.within(p.coordinates(), 0.1), index.within(p.coordinates(), 0.1));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "per-axis gap")]
+    fn kdtree_rejects_metric_without_axis_bound() {
+        let points = vec![Point::new(vec![0.0, 1.0]), Point::new(vec![1.0, 0.0])];
+
+        NeighborSearch::KDTree.build::<SquaredEuclidean>(&points, 0.5);
+    }
+
+    #[test]
+    #[should_panic(expected = "found an empty index")]
+    fn assign_nearest_rejects_empty_index() {
+        let index = NeighborSearch::BruteForce.build::<Euclidean>(&[], 0.5);
+
+        assign_nearest(&[Point::new(vec![0.0, 1.0])], &*index);
+    }
+}
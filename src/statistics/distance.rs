@@ -8,10 +8,11 @@ pub struct Hamming;
 pub struct Chebyshev;
 pub struct Manhattan;
 pub struct CosineSimilarity;
+pub struct CosineDistance;
 pub struct Mahalanobis;
 pub struct Minkowski;
 
-pub trait Distance {
+pub trait Distance: Send + Sync {
     fn distance(_: &[f64], _: &[f64]) -> f64 {
         unimplemented!()
     }
@@ -19,6 +20,9 @@ pub trait Distance {
     fn distance_with_parameter(_: &[f64], _: &[f64], _: f64) -> f64 { unimplemented!() }
 
     fn distance_with_covariance(_: &[f64], _: &[f64], _: &[&[f64]]) -> f64 { unimplemented!() }
+
+    /// Whether `distance` is never below the gap along any single axis, which kd-tree and grid searches prune on
+    fn bounds_axis_gap() -> bool { false }
 }
 
 impl Distance for SquaredEuclidean {
@@ -39,6 +43,8 @@ impl Distance for Euclidean {
             .sum::<f64>())
             .sqrt()
     }
+
+    fn bounds_axis_gap() -> bool { true }
 }
 
 impl Distance for Hamming {
@@ -58,6 +64,8 @@ impl Distance for Chebyshev {
             .max_by(|x, y| x.partial_cmp(&y).unwrap_or(Ordering::Equal))
             .unwrap()
     }
+
+    fn bounds_axis_gap() -> bool { true }
 }
 
 impl Distance for Manhattan {
@@ -68,6 +76,8 @@ impl Distance for Manhattan {
             .map(|(x, y)| (x - y).abs())
             .sum()
     }
+
+    fn bounds_axis_gap() -> bool { true }
 }
 
 impl Distance for CosineSimilarity {
@@ -82,6 +92,13 @@ impl Distance for CosineSimilarity {
     }
 }
 
+impl Distance for CosineDistance {
+    #[inline]
+    fn distance(a: &[f64], b: &[f64]) -> f64 {
+        1.0 - CosineSimilarity::distance(a, b)
+    }
+}
+
 impl Distance for Mahalanobis {
     #[inline]
     fn distance_with_covariance(observation: &[f64], mean: &[f64], observations: &[&[f64]]) -> f64 {
@@ -1,8 +1,11 @@
 use statistics::distance::{Distance, SquaredEuclidean};
 use point::Point;
+use std::f64;
 use std::f64::consts::PI;
 use nalgebra::*;
 use std::cmp::Ordering;
+use rand;
+use rand::Rng;
 
 pub struct Statistics;
 
@@ -95,6 +98,264 @@ impl Statistics {
         return covariance_matrix;
     }
 
+    /**
+     * Cholesky decomposition of a symmetric positive definite matrix.
+     * @param matrix the matrix to decompose.
+     * @return the lower triangular L such that L * L^T = matrix, or None if it is not positive definite.
+     */
+    pub fn cholesky(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+        let n = matrix.len();
+        let mut lower = vec![vec![0.0; n]; n];
+
+        for i in 0..n {
+            for j in 0..(i + 1) {
+                let sum = matrix[i][j] - (0..j).map(|k| lower[i][k] * lower[j][k]).sum::<f64>();
+
+                if i == j {
+                    if sum <= 0.0 || !sum.is_finite() {
+                        return None;
+                    }
+
+                    lower[i][i] = sum.sqrt();
+                } else {
+                    lower[i][j] = sum / lower[j][j];
+                }
+            }
+        }
+
+        Some(lower)
+    }
+
+    /**
+     * Solves L * x = b by forward substitution.
+     * @param lower a lower triangular matrix with non-zero diagonal.
+     * @param b the right hand side.
+     * @return the solution x.
+     */
+    pub fn forward_substitution(lower: &[Vec<f64>], b: &[f64]) -> Vec<f64> {
+        let mut x = vec![0.0; b.len()];
+
+        for i in 0..b.len() {
+            x[i] = (b[i] - (0..i).map(|k| lower[i][k] * x[k]).sum::<f64>()) / lower[i][i];
+        }
+
+        x
+    }
+
+    /**
+     * Numerically stable log(sum(exp(values))).
+     */
+    #[inline]
+    pub fn log_sum_exp(values: &[f64]) -> f64 {
+        let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+        match max.is_finite() {
+            true => max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln(),
+            false => max
+        }
+    }
+
+    /**
+     * Eigen-decomposition of a symmetric matrix by cyclic Jacobi rotations.
+     * @param matrix the symmetric matrix to decompose.
+     * @return the eigenvalues in decreasing order and the matching unit eigenvectors.
+     */
+    pub fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let n = matrix.len();
+        let mut a = matrix.to_vec();
+        let mut v: Vec<Vec<f64>> = (0..n).map(|i| (0..n).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+
+        let norm = a.iter().flat_map(|row| row.iter()).map(|x| x * x).sum::<f64>();
+
+        for _ in 0..100 {
+            let off_diagonal = (0..n).flat_map(|p| ((p + 1)..n).map(move |q| (p, q))).map(|(p, q)| a[p][q] * a[p][q]).sum::<f64>();
+            if off_diagonal <= 1e-30 * norm || off_diagonal == 0.0 {
+                break;
+            }
+
+            for p in 0..n {
+                for q in (p + 1)..n {
+                    if a[p][q] == 0.0 {
+                        continue;
+                    }
+
+                    let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                    let c = 1.0 / (t * t + 1.0).sqrt();
+                    let s = t * c;
+
+                    for k in 0..n {
+                        let (akp, akq) = (a[k][p], a[k][q]);
+                        a[k][p] = c * akp - s * akq;
+                        a[k][q] = s * akp + c * akq;
+                    }
+
+                    for k in 0..n {
+                        let (apk, aqk) = (a[p][k], a[q][k]);
+                        a[p][k] = c * apk - s * aqk;
+                        a[q][k] = s * apk + c * aqk;
+                    }
+
+                    for k in 0..n {
+                        let (vkp, vkq) = (v[k][p], v[k][q]);
+                        v[k][p] = c * vkp - s * vkq;
+                        v[k][q] = s * vkp + c * vkq;
+                    }
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|i, j| a[*j][*j].partial_cmp(&a[*i][*i]).unwrap_or(Ordering::Equal));
+
+        (order.iter().map(|i| a[*i][*i]).collect(), order.iter().map(|i| v.iter().map(|row| row[*i]).collect()).collect())
+    }
+
+    /**
+     * Largest eigenpairs of a symmetric operator by Lanczos iteration with full reorthogonalisation.
+     * @param n the dimension of the operator.
+     * @param multiply computes the product of the operator with a vector.
+     * @param no_eigenvectors the number of eigenpairs to return.
+     * @param no_iterations the size of the Krylov subspace, at least no_eigenvectors.
+     * @return the eigenvalues in decreasing order and the matching unit eigenvectors.
+     */
+    pub fn lanczos<F: Fn(&[f64]) -> Vec<f64>>(n: usize, multiply: F, no_eigenvectors: usize, no_iterations: usize) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let mut rng = rand::thread_rng();
+        let m = no_iterations.max(no_eigenvectors).min(n);
+
+        let mut basis: Vec<Vec<f64>> = Vec::with_capacity(m);
+        let mut alphas = Vec::with_capacity(m);
+        let mut betas = Vec::with_capacity(m);
+        let mut q: Vec<f64> = (0..n).map(|_| rng.next_f64() - 0.5).collect();
+        Self::normalize(&mut q);
+
+        for j in 0..m {
+            let mut w = multiply(&q);
+            alphas.push(Self::dot(&w, &q));
+            basis.push(q);
+
+            if j + 1 == m {
+                break;
+            }
+
+            // Orthogonalising twice against the whole basis keeps it orthogonal in floating point
+            for _ in 0..2 {
+                Self::orthogonalize(&mut w, &basis);
+            }
+
+            let mut beta = Self::normalize(&mut w);
+
+            // The Krylov subspace is invariant, so continue from a fresh direction
+            if beta < 1e-10 {
+                w = (0..n).map(|_| rng.next_f64() - 0.5).collect();
+                for _ in 0..2 {
+                    Self::orthogonalize(&mut w, &basis);
+                }
+
+                Self::normalize(&mut w);
+                beta = 0.0;
+            }
+
+            betas.push(beta);
+            q = w;
+        }
+
+        let size = alphas.len();
+        let tridiagonal: Vec<Vec<f64>> = (0..size).map(|i| (0..size).map(|j| {
+            match (i as isize - j as isize).abs() {
+                0 => alphas[i],
+                1 => betas[i.min(j)],
+                _ => 0.0
+            }
+        }).collect()).collect();
+
+        let (values, vectors) = Self::symmetric_eigen(&tridiagonal);
+
+        let ritz_vectors = vectors.iter().take(no_eigenvectors).map(|s| {
+            (0..n).map(|i| s.iter().zip(basis.iter()).map(|(s_j, b)| s_j * b[i]).sum::<f64>()).collect()
+        }).collect();
+
+        (values.into_iter().take(no_eigenvectors).collect(), ritz_vectors)
+    }
+
+    #[inline]
+    fn dot(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    }
+
+    // Scales to unit length and returns the original length
+    #[inline]
+    fn normalize(vector: &mut [f64]) -> f64 {
+        let length = Self::dot(vector, vector).sqrt();
+        if length > 0.0 {
+            for x in vector.iter_mut() {
+                *x /= length;
+            }
+        }
+
+        length
+    }
+
+    #[inline]
+    fn orthogonalize(vector: &mut [f64], basis: &[Vec<f64>]) {
+        for b in basis {
+            let projection = Self::dot(vector, b);
+            for (x, y) in vector.iter_mut().zip(b.iter()) {
+                *x -= projection * y;
+            }
+        }
+    }
+
+    /**
+     * Cumulative distribution function of the standard normal distribution.
+     */
+    #[inline]
+    pub fn normal_cdf(x: f64) -> f64 {
+        // Complementary error function of |x| / sqrt(2), with fractional error below 1.2e-7
+        let z = x.abs() / 2f64.sqrt();
+        let t = 1.0 / (1.0 + 0.5 * z);
+        let erfc = t * (-z * z - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418 + t * (-0.18628806 +
+                   t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))))).exp();
+
+        match x >= 0.0 {
+            true => 1.0 - 0.5 * erfc,
+            false => 0.5 * erfc
+        }
+    }
+
+    /**
+     * Anderson-Darling test of normality with estimated mean and variance.
+     * @param values the sample, at least two values.
+     * @return the statistic A*^2 corrected for the sample size, and its approximate p-value.
+     */
+    pub fn anderson_darling(values: &[f64]) -> (f64, f64) {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let deviation = (values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0)).sqrt();
+
+        let mut cdf: Vec<f64> = values.iter().map(|v| Self::normal_cdf((v - mean) / deviation).max(f64::MIN_POSITIVE).min(1.0 - f64::EPSILON)).collect();
+        cdf.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let sum = cdf.iter().zip(cdf.iter().rev()).enumerate().map(|(i, (low, high))| {
+            (2.0 * i as f64 + 1.0) * (low.ln() + (1.0 - high).ln())
+        }).sum::<f64>();
+
+        // D'Agostino and Stephens, Goodness-of-Fit Techniques, table 4.9, where the p-value fits below hold for this correction
+        let a = (-n - sum / n) * (1.0 + 0.75 / n + 2.25 / (n * n));
+
+        let p_value = if a >= 0.6 {
+            (1.2937 - 5.709 * a + 0.0186 * a * a).exp()
+        } else if a >= 0.34 {
+            (0.9177 - 4.279 * a - 1.38 * a * a).exp()
+        } else if a >= 0.2 {
+            1.0 - (-8.318 + 42.796 * a - 59.938 * a * a).exp()
+        } else {
+            1.0 - (-13.436 + 101.14 * a - 223.73 * a * a).exp()
+        };
+
+        (a, p_value.max(0.0).min(1.0))
+    }
+
     /**
      * Calculates the BIC for single cluster.
      * @param n the total number of samples.
@@ -102,7 +363,7 @@ impl Statistics {
      * @param distortion the distortion of clusters (i.e. variance).
      * @return the BIC score.
      */
-    fn bic_single(n: f64, d: f64, distortion: f64) -> f64 {
+    pub fn bic_single(n: f64, d: f64, distortion: f64) -> f64 {
         let variance = distortion / (n - 1.0);
 
         let p1 = -n * (2.0 * PI).ln();
@@ -121,7 +382,7 @@ impl Statistics {
      * @param cluster_size the number of samples in each cluster.
      * @return the BIC score.
      */
-    fn bic(k: f64, n: f64, d: f64, distortion: f64, cluster_size: &[f64]) -> f64 {
+    pub fn bic(k: f64, n: f64, d: f64, distortion: f64, cluster_size: &[f64]) -> f64 {
         let variance = distortion / (n - k);
 
         (0..k as usize).into_iter().map(|i| Self::log_likelihood(k, n, cluster_size[i], d, variance)).sum::<f64>() - 0.5 * (k + k * d) * n.ln()
@@ -137,7 +398,7 @@ impl Statistics {
      * @param variance the estimated variance of clusters.
      * @return the likelihood estimate
      */
-    fn log_likelihood(k: f64, n: f64, ni: f64, d: f64, variance:f64) -> f64 {
+    pub fn log_likelihood(k: f64, n: f64, ni: f64, d: f64, variance:f64) -> f64 {
         let p1 = -ni * (2.0 * PI).ln();
         let p2 = -ni * d * variance.ln();
         let p3 = -(ni - k);
@@ -150,6 +411,19 @@ impl Statistics {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rand;
+    use rand::distributions::{IndependentSample, Normal};
+
+    #[test]
+    fn anderson_darling_accepts_gaussian_sample() {
+        let mut rng = rand::thread_rng();
+        let normal = Normal::new(3.0, 2.0);
+        let sample: Vec<f64> = (0..500).map(|_| normal.ind_sample(&mut rng)).collect();
+        let bimodal: Vec<f64> = sample.iter().enumerate().map(|(i, x)| if i % 2 == 0 { x - 10.0 } else { x + 10.0 }).collect();
+
+        assert!(Statistics::anderson_darling(&sample).1 > 0.0001);
+        assert!(Statistics::anderson_darling(&bimodal).1 < 0.0001);
+    }
 
     /*#[test]
     fn covariance_is_correct() {